@@ -0,0 +1,84 @@
+/*
+ * Compares `helpers::ints` against the `Regex` captures it replaces.
+ * Run with `cargo bench --bench ints`.
+ */
+use std::time::Instant;
+
+use advent_of_code::helpers::ints;
+use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
+use regex::Regex;
+
+const LINES: usize = 200_000;
+
+fn time<T: std::fmt::Debug + PartialEq>(name: &str, f: impl Fn() -> T) -> T {
+    let timer = Instant::now();
+    let result = f();
+    println!(
+        "{:<24} {}(elapsed: {:.2?}){}",
+        name,
+        ANSI_ITALIC,
+        timer.elapsed(),
+        ANSI_RESET
+    );
+    result
+}
+
+fn main() {
+    let moves = (0..LINES)
+        .map(|n| format!("move {} from {} to {}", n % 37, n % 9 + 1, n % 7 + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let sensors = (0..LINES as i64)
+        .map(|n| {
+            format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                n * 7919 % 4_000_000 - 2_000_000,
+                n * 104_729 % 4_000_000,
+                -n,
+                n * 3
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    println!("{}day 05 style moves{}", ANSI_BOLD, ANSI_RESET);
+    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
+    let expected = time("regex", || {
+        moves
+            .lines()
+            .map(|line| {
+                let cap = re.captures(line).unwrap();
+                (1..=3)
+                    .map(|i| cap[i].parse::<usize>().unwrap())
+                    .sum::<usize>()
+            })
+            .sum::<usize>()
+    });
+    let actual = time("helpers::ints", || {
+        moves
+            .lines()
+            .map(|line| ints::<usize>(line).sum::<Result<usize, _>>().unwrap())
+            .sum::<usize>()
+    });
+    assert_eq!(expected, actual);
+
+    println!("{}day 15 style sensors{}", ANSI_BOLD, ANSI_RESET);
+    let re =
+        Regex::new(r"x=(-?\d+), y=(-?\d+): closest beacon is at x=(-?\d+), y=(-?\d+)").unwrap();
+    let expected = time("regex", || {
+        sensors
+            .lines()
+            .map(|line| {
+                let cap = re.captures(line).unwrap();
+                (1..=4).map(|i| cap[i].parse::<i64>().unwrap()).sum::<i64>()
+            })
+            .sum::<i64>()
+    });
+    let actual = time("helpers::ints", || {
+        sensors
+            .lines()
+            .map(|line| ints::<i64>(line).sum::<Result<i64, _>>().unwrap())
+            .sum::<i64>()
+    });
+    assert_eq!(expected, actual);
+}
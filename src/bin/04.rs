@@ -1,12 +1,19 @@
+use advent_of_code::helpers::{ints, IntError};
 use itertools::Itertools;
 
+pub fn parse(input: &str) -> Result<Vec<(u8, u8, u8, u8)>, IntError> {
+    let sections = input
+        .lines() // "2-4,6-8"
+        .flat_map(ints::<u8>) // 2 4 6 8
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sections.into_iter().tuples().collect())
+}
+
 pub fn part_one(input: &str) -> Option<u32> {
     Some(
-        input
-            .lines() // "2-4,6-8"
-            .flat_map(|line| line.split(',').flat_map(|s| s.split('-'))) // "2-4" to "2" "4"
-            .map(|n| n.parse::<u8>().unwrap()) // 2
-            .tuples::<(u8, u8, u8, u8)>()
+        parse(input)
+            .ok()?
+            .iter()
             .filter(|(a1, a2, b1, b2)| a1 >= b1 && a2 <= b2 || b1 >= a1 && b2 <= a2)
             .count() as u32,
     )
@@ -14,11 +21,9 @@ pub fn part_one(input: &str) -> Option<u32> {
 
 pub fn part_two(input: &str) -> Option<u32> {
     Some(
-        input
-            .lines()
-            .flat_map(|line| line.split(',').flat_map(|s| s.split('-')))
-            .map(|n| n.parse::<u8>().unwrap())
-            .tuples::<(u8, u8, u8, u8)>()
+        parse(input)
+            .ok()?
+            .iter()
             .filter(|(a1, a2, b1, b2)| !(a2 < b1 || b2 < a1))
             .count() as u32,
     )
@@ -26,6 +31,10 @@ pub fn part_two(input: &str) -> Option<u32> {
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 4);
+    if let Err(e) = parse(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -45,4 +54,13 @@ mod tests {
         let input = advent_of_code::read_file("examples", 4);
         assert_eq!(part_two(&input), Some(4));
     }
+
+    #[test]
+    fn test_out_of_range() {
+        assert_eq!(
+            parse("2-4,6-300").unwrap_err().to_string(),
+            "300 doesn't fit in a u8"
+        );
+        assert_eq!(part_one("2-4,6-300"), None);
+    }
 }
@@ -1,122 +1,277 @@
-use regex::Regex;
+use std::fmt;
 
-pub fn part_one(input: &str) -> Option<String> {
-    let mut crate_data = vec![];
-    let mut crates: Vec<Vec<char>>;
-    let mut moves = vec![];
-
-    let mut count = 0;
-    let mut parsing_crates = true;
-
-    for line in input.lines() {
-        if line == "" {
-            continue;
-        } else if line.contains(" 1   ") {
-            // capture down to 1 2 ... n
-            parsing_crates = false;
-            count = (line.len() + 1) / 4;
-        } else if parsing_crates {
-            crate_data.push(line);
-        } else {
-            moves.push(line);
-        }
-    }
-    // then reverse and loop to build up each columns boxes
-    crate_data.reverse();
-    crates = vec![vec![]; count];
-    for line in crate_data {
-        for (idx, c) in line.char_indices() {
-            if idx > 0 && (idx - 1) % 4 == 0 && c != ' ' {
-                // indexes are 2 6 10 etc but zero based
-                crates[(idx - 1) / 4].push(c);
+use advent_of_code::helpers::ints;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StackError {
+    MissingLabels,
+    BadLabel(String),
+    // (line of the drawing, column)
+    CrateWithoutStack(usize, usize),
+    BadMove(String),
+    UnknownStack(Move, usize),
+    // (move, crates actually on the stack)
+    Underflow(Move, usize),
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackError::MissingLabels => write!(f, "drawing has no stack label line"),
+            StackError::BadLabel(label) => write!(f, "stack label {:?} is not a number", label),
+            StackError::CrateWithoutStack(line, column) => write!(
+                f,
+                "crate on line {}, column {} is not above any stack label",
+                line, column
+            ),
+            StackError::BadMove(line) => write!(f, "could not read move {:?}", line),
+            StackError::UnknownStack(m, id) => write!(f, "{}: there is no stack {}", m, id),
+            StackError::Underflow(m, available) => {
+                write!(f, "{}: stack {} only has {} crate(s)", m, m.from, available)
             }
         }
     }
+}
 
-    // now process the moves on the columns of boxes
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-    for line in moves {
-        let cap = re.captures(line).unwrap();
-        let num = cap[1].parse::<usize>().unwrap();
-        let from = cap[2].parse::<usize>().unwrap() - 1;
-        let to = cap[3].parse::<usize>().unwrap() - 1;
-        for _ in 0..num {
-            let val = crates[from].pop().unwrap();
-            crates[to].push(val);
+impl std::error::Error for StackError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    count: usize,
+    // stack ids, as labelled in the drawing
+    from: usize,
+    to: usize,
+}
+
+impl Move {
+    // "move 1 from 2 to 1"
+    pub fn parse(line: &str) -> Result<Self, StackError> {
+        match ints::<usize>(line)
+            .collect::<Result<Vec<_>, _>>()
+            .as_deref()
+        {
+            Ok(&[count, from, to]) => Ok(Self { count, from, to }),
+            _ => Err(StackError::BadMove(line.to_string())),
         }
     }
+}
 
-    // finally, grab the last crate in each column
-    let mut result = String::new();
-    for col in 0..crates.len() {
-        result.push(crates[col][crates[col].len() - 1]);
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
     }
-    Some(result)
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    // no refactoring here, just C+P from part one!
-    let mut crate_data = vec![];
-    let mut crates: Vec<Vec<char>>;
-    let mut moves = vec![];
-
-    let mut count = 0;
-    let mut parsing_crates = true;
-
-    for line in input.lines() {
-        if line == "" {
-            continue;
-        } else if line.contains(" 1   ") {
-            // capture down to 1 2 ... n
-            parsing_crates = false;
-            count = (line.len() + 1) / 4;
-        } else if parsing_crates {
-            crate_data.push(line);
-        } else {
-            moves.push(line);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateStacks {
+    // the id printed under each stack, in drawing order
+    labels: Vec<usize>,
+    // bottom crate first
+    stacks: Vec<Vec<char>>,
+}
+
+impl CrateStacks {
+    // the last line of the drawing labels the stacks, every crate sits above one of the labels
+    pub fn parse(drawing: &str) -> Result<Self, StackError> {
+        let mut lines = drawing.lines().collect::<Vec<_>>();
+        let label_line = lines.pop().ok_or(StackError::MissingLabels)?;
+
+        // (id, first column, last column) for each label
+        let mut labels = vec![];
+        let mut column = 0;
+        for token in label_line.split(' ') {
+            if !token.is_empty() {
+                let id = token
+                    .parse::<usize>()
+                    .map_err(|_| StackError::BadLabel(token.to_string()))?;
+                labels.push((id, column, column + token.len() - 1));
+            }
+            column += token.len() + 1;
         }
-    }
-    // then reverse and loop to build up each columns boxes
-    crate_data.reverse();
-    crates = vec![vec![]; count];
-    for line in crate_data {
-        for (idx, c) in line.char_indices() {
-            if idx > 0 && (idx - 1) % 4 == 0 && c != ' ' {
-                // indexes are 2 6 10 etc but zero based
-                crates[(idx - 1) / 4].push(c);
+        if labels.is_empty() {
+            return Err(StackError::MissingLabels);
+        }
+
+        // then loop bottom up to build up each columns boxes
+        let mut stacks = vec![vec![]; labels.len()];
+        for (y, line) in lines.iter().enumerate().rev() {
+            for (x, _) in line.match_indices('[') {
+                // "[Z]" belongs to whichever label overlaps it
+                let stack = labels
+                    .iter()
+                    .position(|(_, start, end)| *start <= x + 2 && x <= *end)
+                    .ok_or(StackError::CrateWithoutStack(y + 1, x + 1))?;
+                let c = line[x + 1..].chars().next().unwrap_or(' ');
+                stacks[stack].push(c);
             }
         }
+        Ok(Self {
+            labels: labels.into_iter().map(|(id, _, _)| id).collect(),
+            stacks,
+        })
     }
 
-    // now process the moves on the columns of boxes
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-    for line in moves {
-        let cap = re.captures(line).unwrap();
-        let num = cap[1].parse::<usize>().unwrap();
-        let from = cap[2].parse::<usize>().unwrap() - 1;
-        let to = cap[3].parse::<usize>().unwrap() - 1;
+    fn stack_mut(&mut self, m: &Move, id: usize) -> Result<&mut Vec<char>, StackError> {
+        let idx = self
+            .labels
+            .iter()
+            .position(|label| *label == id)
+            .ok_or(StackError::UnknownStack(*m, id))?;
+        Ok(&mut self.stacks[idx])
+    }
 
-        let mut tmp = vec![];
-        for _ in 0..num {
-            tmp.push(crates[from].pop().unwrap());
+    // takes the top `m.count` crates off the `from` stack, top crate last
+    fn lift(&mut self, m: &Move) -> Result<Vec<char>, StackError> {
+        // check the destination exists before touching anything, so a bad move leaves the stacks alone
+        self.stack_mut(m, m.to)?;
+        let from = self.stack_mut(m, m.from)?;
+        if from.len() < m.count {
+            return Err(StackError::Underflow(*m, from.len()));
         }
-        tmp.reverse();
-        crates[to].extend_from_slice(&tmp);
-        // crates[to].extend_from_slice(crates[from][num - 1..crates[from].len()]);
+        Ok(from.split_off(from.len() - m.count))
+    }
+
+    // grab the last crate in each column
+    pub fn tops(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
+    }
+
+    // runs every move, yielding the stacks as they are after each one
+    pub fn replay<'a>(
+        mut self,
+        moves: &'a [Move],
+        crane: &'a dyn Crane,
+    ) -> impl Iterator<Item = Result<(Move, CrateStacks), StackError>> + 'a {
+        moves.iter().map(move |m| {
+            crane.apply(&mut self, m)?;
+            Ok((*m, self.clone()))
+        })
+    }
+}
+
+// draws the stacks back out the same way the puzzle input does
+impl fmt::Display for CrateStacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+        for row in (0..height).rev() {
+            let line = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(row) {
+                    Some(c) => format!("[{}]", c),
+                    None => "   ".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", line)?;
+        }
+        let labels = self
+            .labels
+            .iter()
+            .map(|id| format!("{:^3}", id))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{}", labels)
+    }
+}
+
+pub trait Crane {
+    fn apply(&self, stacks: &mut CrateStacks, m: &Move) -> Result<(), StackError>;
+}
+
+// moves crates one at a time, so a multi-crate move reverses their order
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn apply(&self, stacks: &mut CrateStacks, m: &Move) -> Result<(), StackError> {
+        let lifted = stacks.lift(m)?;
+        stacks.stack_mut(m, m.to)?.extend(lifted.into_iter().rev());
+        Ok(())
     }
+}
+
+// picks up all the crates in one go, keeping their order
+pub struct CrateMover9001;
 
-    // finally, grab the last crate in each column
-    let mut result = String::new();
-    for col in 0..crates.len() {
-        result.push(crates[col][crates[col].len() - 1]);
+impl Crane for CrateMover9001 {
+    fn apply(&self, stacks: &mut CrateStacks, m: &Move) -> Result<(), StackError> {
+        let lifted = stacks.lift(m)?;
+        stacks.stack_mut(m, m.to)?.extend(lifted);
+        Ok(())
     }
-    Some(result)
+}
+
+pub fn parse(input: &str) -> Result<(CrateStacks, Vec<Move>), StackError> {
+    // the drawing is everything up to the first blank line
+    let (drawing, moves) = input.split_once("\n\n").unwrap_or((input, ""));
+    Ok((
+        CrateStacks::parse(drawing)?,
+        moves
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Move::parse)
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+pub fn run(input: &str, crane: &dyn Crane) -> Result<String, StackError> {
+    let (mut stacks, moves) = parse(input)?;
+    for m in moves.iter() {
+        crane.apply(&mut stacks, m)?;
+    }
+    Ok(stacks.tops())
+}
+
+pub fn part_one(input: &str) -> Option<String> {
+    run(input, &CrateMover9000).ok()
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    run(input, &CrateMover9001).ok()
+}
+
+// step through the moves one at a time, printing the stacks after each one
+fn replay(input: &str, crane: &dyn Crane) -> Result<(), StackError> {
+    let (stacks, moves) = parse(input)?;
+    println!("{}\n", stacks);
+    for step in stacks.replay(&moves, crane) {
+        let (m, stacks) = step?;
+        println!("{}\n{}\n", m, stacks);
+    }
+    Ok(())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 5);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+
+    // e.g. `cargo solve 05 -- --replay 9001`
+    let mut args = pico_args::Arguments::from_env();
+    let crane: &dyn Crane = match args.opt_value_from_str::<_, u32>("--replay") {
+        Ok(Some(9000)) => &CrateMover9000,
+        Ok(Some(9001)) => &CrateMover9001,
+        Ok(Some(model)) => {
+            eprintln!("Unknown crane model {}, expected 9000 or 9001", model);
+            std::process::exit(1);
+        }
+        Ok(None) => {
+            // the parts just come back empty on a bad drawing or move, so say what's wrong first
+            if let Err(e) = parse(input) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = replay(input, crane) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }
 
 #[cfg(test)]
@@ -134,4 +289,65 @@ mod tests {
         let input = advent_of_code::read_file("examples", 5);
         assert_eq!(part_two(&input), Some("MCD".to_owned()));
     }
+
+    #[test]
+    fn test_display_round_trips() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (stacks, moves) = parse(&input).unwrap();
+        let drawing = input.split("\n\n").next().unwrap();
+        assert_eq!(stacks.to_string(), drawing.trim_end_matches('\n'));
+
+        let (m, after) = stacks
+            .replay(&moves, &CrateMover9000)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(m.to_string(), "move 1 from 2 to 1");
+        assert_eq!(
+            after.to_string(),
+            "[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn test_many_stacks_and_trimmed_labels() {
+        let input = "[A]                                    [K]\n[B] [C] [D] [E] [F] [G] [H] [I] [J] [L] [M]\n 1   2   3   4   5   6   7   8   9   10  11\n\nmove 2 from 11 to 10\nmove 1 from 10 to 1";
+        assert_eq!(run(input, &CrateMover9000), Ok("MCDEFGHIJK".to_string()));
+        assert_eq!(run(input, &CrateMover9001), Ok("KCDEFGHIJM".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (mut stacks, _) = parse(&input).unwrap();
+        let m = Move::parse("move 4 from 2 to 1").unwrap();
+        assert_eq!(
+            CrateMover9001.apply(&mut stacks, &m),
+            Err(StackError::Underflow(m, 3))
+        );
+        assert_eq!(
+            CrateMover9000.apply(&mut stacks, &Move::parse("move 1 from 4 to 1").unwrap()),
+            Err(StackError::UnknownStack(
+                Move::parse("move 1 from 4 to 1").unwrap(),
+                4
+            ))
+        );
+        assert_eq!(
+            Move::parse("move 3 from 2"),
+            Err(StackError::BadMove("move 3 from 2".to_string()))
+        );
+        let huge = "move 1 from 99999999999999999999999 to 2";
+        assert_eq!(
+            Move::parse(huge),
+            Err(StackError::BadMove(huge.to_string()))
+        );
+        assert_eq!(
+            CrateStacks::parse("    [A]\n 1 "),
+            Err(StackError::CrateWithoutStack(1, 5))
+        );
+        assert_eq!(
+            CrateStacks::parse("[A]\n a "),
+            Err(StackError::BadLabel("a".to_string()))
+        );
+    }
 }
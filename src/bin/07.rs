@@ -1,220 +1,356 @@
-use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+use std::{collections::BTreeMap, fmt};
 
-#[derive(PartialEq)]
-struct Folder {
+// index of a directory in the filesystem arena
+pub type DirId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    Dir(DirId),
+    File(u64),
+}
+
+#[derive(Debug)]
+pub struct Dir {
     name: String,
-    files: HashMap<String, u128>,
-    subdirs: HashMap<String, Rc<RefCell<Folder>>>,
-    parent: Option<Rc<RefCell<Folder>>>,
-    size: u128, // size of all the files in this folder and all subfolders
-}
-
-impl Folder {
-    pub fn new(name: &str, parent: Option<Rc<RefCell<Folder>>>) -> Folder {
-        Folder {
-            name: name.into(),
-            files: HashMap::new(),
-            subdirs: HashMap::new(),
-            parent,
-            size: 0u128,
+    parent: Option<DirId>,
+    // sorted by name, the same way `tree` lists them
+    entries: BTreeMap<String, Entry>,
+}
+
+// every directory lives in one Vec and refers to the others by index, so no Rc/RefCell back-pointers to trip over
+#[derive(Debug)]
+pub struct FileSystem {
+    dirs: Vec<Dir>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub const ROOT: DirId = 0;
+
+    pub fn new() -> Self {
+        Self {
+            dirs: vec![Dir {
+                name: String::from("/"),
+                parent: None,
+                entries: BTreeMap::new(),
+            }],
         }
     }
 
-    pub fn find_less_than_100k(&self, acc: &mut u128) -> () {
-        // println!("{} -> {}", self.name, self.size);
-        if self.size < 100000 {
-            *acc += self.size;
+    pub fn parent(&self, dir: DirId) -> Option<DirId> {
+        self.dirs[dir].parent
+    }
+
+    // full path of a directory, e.g. "/a/e"
+    pub fn path(&self, dir: DirId) -> String {
+        match self.dirs[dir].parent {
+            None => String::from("/"),
+            Some(Self::ROOT) => format!("/{}", self.dirs[dir].name),
+            Some(parent) => format!("{}/{}", self.path(parent), self.dirs[dir].name),
         }
-        for subdir in self.subdirs.values() {
-            subdir.borrow().find_less_than_100k(acc);
+    }
+
+    // follows a relative or absolute path from `cwd`, ".." at the root stays at the root like a real shell
+    pub fn resolve(&self, cwd: DirId, path: &str) -> Option<DirId> {
+        let start = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            cwd
+        };
+        path.split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .try_fold(start, |dir, part| match part {
+                ".." => Some(self.dirs[dir].parent.unwrap_or(Self::ROOT)),
+                name => match self.dirs[dir].entries.get(name) {
+                    Some(Entry::Dir(child)) => Some(*child),
+                    _ => None,
+                },
+            })
+    }
+
+    // returns the existing subdirectory if there already is one, so listing a directory twice is harmless
+    pub fn add_dir(&mut self, parent: DirId, name: &str) -> DirId {
+        if let Some(Entry::Dir(existing)) = self.dirs[parent].entries.get(name) {
+            return *existing;
         }
+        let id = self.dirs.len();
+        self.dirs.push(Dir {
+            name: name.to_string(),
+            parent: Some(parent),
+            entries: BTreeMap::new(),
+        });
+        self.dirs[parent]
+            .entries
+            .insert(name.to_string(), Entry::Dir(id));
+        id
     }
 
-    pub fn find_all_sizes(&self, acc: &mut Vec<u128>) -> () {
-        // println!("{} -> {}", self.name, self.size);
-        acc.push(self.size);
-        for subdir in self.subdirs.values() {
-            subdir.borrow().find_all_sizes(acc);
+    // overwrites rather than adds, so listing a directory twice doesn't double count
+    pub fn add_file(&mut self, dir: DirId, name: &str, size: u64) {
+        self.dirs[dir]
+            .entries
+            .insert(name.to_string(), Entry::File(size));
+    }
+
+    // size of all the files in each folder and all its subfolders, indexed by DirId
+    pub fn sizes(&self) -> Vec<u64> {
+        let mut sizes = self
+            .dirs
+            .iter()
+            .map(|dir| {
+                dir.entries
+                    .values()
+                    .map(|entry| match entry {
+                        Entry::File(size) => *size,
+                        Entry::Dir(_) => 0,
+                    })
+                    .sum()
+            })
+            .collect::<Vec<u64>>();
+        // children are always created after their parent, so walking backwards rolls every size up in one pass
+        for dir in (1..self.dirs.len()).rev() {
+            let parent = self.dirs[dir].parent.unwrap();
+            sizes[parent] += sizes[dir];
         }
+        sizes
+    }
+
+    pub fn size(&self, dir: DirId) -> u64 {
+        self.dirs[dir]
+            .entries
+            .values()
+            .map(|entry| match entry {
+                Entry::File(size) => *size,
+                Entry::Dir(child) => self.size(*child),
+            })
+            .sum()
+    }
+
+    fn render(&self, dir: DirId, prefix: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.dirs[dir].entries.len();
+        for (i, (name, entry)) in self.dirs[dir].entries.iter().enumerate() {
+            let last = i == count - 1;
+            let branch = if last { "└── " } else { "├── " };
+            match entry {
+                Entry::File(size) => writeln!(f, "{}{}{} ({})", prefix, branch, name, size)?,
+                Entry::Dir(child) => {
+                    writeln!(f, "{}{}{}", prefix, branch, name)?;
+                    let indent = if last { "    " } else { "│   " };
+                    self.render(*child, &format!("{}{}", prefix, indent), f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// something found while walking the filesystem, sizes of dirs include everything beneath them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Node<'a> {
+    Dir {
+        id: DirId,
+        size: u64,
+    },
+    File {
+        dir: DirId,
+        name: &'a str,
+        size: u64,
+    },
+}
+
+impl Node<'_> {
+    pub fn size(&self) -> u64 {
+        match self {
+            Node::Dir { size, .. } | Node::File { size, .. } => *size,
+        }
+    }
+
+    // "h.lst" -> Some("lst"), directories and files without a dot have no extension
+    pub fn extension(&self) -> Option<&str> {
+        match self {
+            Node::File { name, .. } => name.rsplit_once('.').map(|(_, ext)| ext),
+            Node::Dir { .. } => None,
+        }
+    }
+}
+
+// how big the disk is, and how much free space an update needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disk {
+    pub capacity: u64,
+    pub required: u64,
+}
+
+impl Disk {
+    // the device from the puzzle
+    pub const DEVICE: Disk = Disk {
+        capacity: 70000000,
+        required: 30000000,
+    };
+
+    // how much more space has to be freed up when `used` is taken
+    pub fn shortfall(&self, used: u64) -> u64 {
+        let unused = self.capacity.saturating_sub(used);
+        self.required.saturating_sub(unused)
+    }
+}
+
+impl FileSystem {
+    // every dir and file, depth first from the root in `tree` order
+    pub fn walk(&self) -> impl Iterator<Item = Node<'_>> {
+        let sizes = self.sizes();
+        let mut stack = vec![Node::Dir {
+            id: Self::ROOT,
+            size: sizes[Self::ROOT],
+        }];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            if let Node::Dir { id, .. } = node {
+                // pushed in reverse so they come back off the stack in name order
+                stack.extend(
+                    self.dirs[id]
+                        .entries
+                        .iter()
+                        .rev()
+                        .map(|(name, entry)| match entry {
+                            Entry::Dir(child) => Node::Dir {
+                                id: *child,
+                                size: sizes[*child],
+                            },
+                            Entry::File(size) => Node::File {
+                                dir: id,
+                                name,
+                                size: *size,
+                            },
+                        }),
+                );
+            }
+            Some(node)
+        })
+    }
+
+    // (dir, size) for every directory
+    pub fn dir_sizes(&self) -> impl Iterator<Item = (DirId, u64)> + '_ {
+        self.walk().filter_map(|node| match node {
+            Node::Dir { id, size } => Some((id, size)),
+            Node::File { .. } => None,
+        })
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = Node<'_>> {
+        self.walk().filter(|node| matches!(node, Node::File { .. }))
+    }
+
+    pub fn dirs_smaller_than(&self, limit: u64) -> impl Iterator<Item = (DirId, u64)> + '_ {
+        self.dir_sizes().filter(move |(_, size)| *size < limit)
+    }
+
+    // the smallest directory that, once deleted, leaves enough room on `disk`
+    pub fn dir_to_delete(&self, disk: &Disk) -> Option<(DirId, u64)> {
+        let needed = disk.shortfall(self.sizes()[Self::ROOT]);
+        self.dir_sizes()
+            .filter(|(_, size)| *size >= needed)
+            .min_by_key(|(_, size)| *size)
+    }
+
+    pub fn largest_files(&self, n: usize) -> Vec<Node<'_>> {
+        let mut files = self.files().collect::<Vec<_>>();
+        files.sort_by_key(|file| std::cmp::Reverse(file.size()));
+        files.truncate(n);
+        files
+    }
+
+    pub fn files_with_extension<'a>(&'a self, ext: &'a str) -> impl Iterator<Item = Node<'a>> {
+        self.files()
+            .filter(move |file| file.extension() == Some(ext))
     }
 }
 
-impl fmt::Display for Folder {
+// draws the filesystem the way `tree` does, with file sizes in brackets
+impl fmt::Display for FileSystem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "({}, {:?}), files: {:?}, acc size: {:?}",
-            self.name,
-            self.subdirs.keys(),
-            self.files,
-            self.size
-        )
+        writeln!(f, "/")?;
+        self.render(Self::ROOT, "", f)
     }
 }
 
-impl fmt::Debug for Folder {
-    // can't print the parent or we stackoverflow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptError {
+    // (line number, reason)
+    Malformed(usize, String),
+    NoSuchDir(usize, String),
+}
+
+impl fmt::Display for TranscriptError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "({}, {:?}), files: {:?}, acc size: {:?}",
-            self.name,
-            self.subdirs.keys(),
-            self.files,
-            self.size
-        )
-    }
-}
-
-pub fn part_one(input: &str) -> Option<u128> {
-    let root = Rc::new(RefCell::new(Folder::new("/", None)));
-    let mut pwd = Rc::clone(&root);
-
-    for line in input.lines() {
-        if line.starts_with("$ cd ") {
-            let cmd = line.replace("$ cd ", "");
-            let name = cmd.as_str();
-            match name {
-                "/" => {
-                    pwd = Rc::clone(&root);
-                }
-                ".." => {
-                    let parent = Rc::clone(&pwd.borrow().parent.as_ref().unwrap());
-                    pwd = Rc::clone(&parent);
-                }
-                _ => {
-                    let d = Rc::clone(&pwd.borrow().subdirs.get(name).unwrap());
-                    pwd = Rc::clone(&d);
-                }
-            }
-        } else if line.contains("$ ls") {
-            continue;
-        } else {
-            // reading files in the current dir
-            if line.starts_with("dir ") {
-                // add to subdirs
-                let dirname = line.replace("dir ", "");
-                let new_dir = Rc::new(RefCell::new(Folder::new(&dirname.clone(), None)));
-                pwd.borrow_mut()
-                    .subdirs
-                    .insert(dirname.clone(), Rc::clone(&new_dir));
-                {
-                    let mut mut_subdir = new_dir.borrow_mut();
-                    mut_subdir.parent = Some(Rc::clone(&pwd));
-                }
-            } else {
-                // add to files
-                let mut split = line.split(" ");
-                let size = split.next().unwrap().parse::<u128>().unwrap();
-                let filename = split.next().unwrap();
-                pwd.borrow_mut()
-                    .files
-                    .entry(filename.to_string())
-                    .and_modify(|val| *val += size)
-                    .or_insert(size);
-                // every time we add a file, let's traverse the dir structure and update all the ancestors so we have an easier time later
-                pwd.borrow_mut().size += size;
-                let mut ptr = Rc::clone(&pwd);
-                loop {
-                    if ptr.borrow().parent.is_none() {
-                        break;
-                    }
-                    let parent = Rc::clone(&ptr.borrow_mut().parent.as_ref().unwrap());
-                    parent.borrow_mut().size += size;
-                    ptr = parent;
-                }
+        match self {
+            TranscriptError::Malformed(line, reason) => write!(f, "line {}: {}", line, reason),
+            TranscriptError::NoSuchDir(line, path) => {
+                write!(f, "line {}: cd: no such directory: {}", line, path)
             }
         }
     }
-
-    // finally, after all this buildup, traverse from the root and gather up the data we need
-    let mut result = 0u128;
-    let ptr = Rc::clone(&root);
-    ptr.borrow().find_less_than_100k(&mut result);
-    Some(result)
 }
 
-pub fn part_two(input: &str) -> Option<u128> {
-    // uhm, yes, refactor this C+P mess, building the structure is the same each time, only the fn changes
-    let root = Rc::new(RefCell::new(Folder::new("/", None)));
-    let mut pwd = Rc::clone(&root);
+impl std::error::Error for TranscriptError {}
 
-    for line in input.lines() {
-        if line.starts_with("$ cd ") {
-            let cmd = line.replace("$ cd ", "");
-            let name = cmd.as_str();
-            match name {
-                "/" => {
-                    pwd = Rc::clone(&root);
-                }
-                ".." => {
-                    let parent = Rc::clone(&pwd.borrow().parent.as_ref().unwrap());
-                    pwd = Rc::clone(&parent);
-                }
-                _ => {
-                    let d = Rc::clone(&pwd.borrow().subdirs.get(name).unwrap());
-                    pwd = Rc::clone(&d);
+// replays a shell session of `cd` and `ls` commands, building up the filesystem it saw
+pub fn parse_transcript(input: &str) -> Result<FileSystem, TranscriptError> {
+    let mut fs = FileSystem::new();
+    let mut pwd = FileSystem::ROOT;
+
+    for (n, line) in input.lines().enumerate().map(|(n, line)| (n + 1, line)) {
+        let malformed = |reason: &str| TranscriptError::Malformed(n, reason.to_string());
+        if let Some(cmd) = line.strip_prefix("$ ") {
+            match cmd.split_once(' ') {
+                Some(("cd", path)) => {
+                    pwd = fs
+                        .resolve(pwd, path)
+                        .ok_or_else(|| TranscriptError::NoSuchDir(n, path.to_string()))?;
                 }
+                None if cmd == "ls" => continue,
+                _ => return Err(malformed(&format!("unknown command {:?}", cmd))),
             }
-        } else if line.contains("$ ls") {
-            continue;
         } else {
-            // reading files in the current dir
-            if line.starts_with("dir ") {
-                // add to subdirs
-                let dirname = line.replace("dir ", "");
-                let new_dir = Rc::new(RefCell::new(Folder::new(&dirname.clone(), None)));
-                pwd.borrow_mut()
-                    .subdirs
-                    .insert(dirname.clone(), Rc::clone(&new_dir));
-                {
-                    let mut mut_subdir = new_dir.borrow_mut();
-                    mut_subdir.parent = Some(Rc::clone(&pwd));
+            // reading the listing of the current dir
+            match line.split_once(' ') {
+                Some(("dir", dirname)) => {
+                    fs.add_dir(pwd, dirname);
                 }
-            } else {
-                // add to files
-                let mut split = line.split(" ");
-                let size = split.next().unwrap().parse::<u128>().unwrap();
-                let filename = split.next().unwrap();
-                pwd.borrow_mut()
-                    .files
-                    .entry(filename.to_string())
-                    .and_modify(|val| *val += size)
-                    .or_insert(size);
-                // every time we add a file, let's traverse the dir structure and update all the ancestors so we have an easier time later
-                pwd.borrow_mut().size += size;
-                let mut ptr = Rc::clone(&pwd);
-                loop {
-                    if ptr.borrow().parent.is_none() {
-                        break;
-                    }
-                    let parent = Rc::clone(&ptr.borrow_mut().parent.as_ref().unwrap());
-                    parent.borrow_mut().size += size;
-                    ptr = parent;
+                Some((size, filename)) => {
+                    let size = size
+                        .parse::<u64>()
+                        .map_err(|_| malformed(&format!("bad file size {:?}", size)))?;
+                    fs.add_file(pwd, filename, size);
                 }
+                None => return Err(malformed(&format!("unexpected output {:?}", line))),
             }
         }
     }
+    Ok(fs)
+}
 
-    // finally, after all this buildup, traverse from the root and gather up the data we need
-    let ptr = Rc::clone(&root);
-    let mut all_sizes: Vec<u128> = vec![];
-    ptr.borrow().find_all_sizes(&mut all_sizes);
-    all_sizes.sort(); // to make it easy to find the value we want
-    let total_fs = root.borrow().size;
-    let unused = 70000000u128 - total_fs;
-    let goal = 30000000u128 - unused;
-    // println!("attempting to find first folder giving us > {}", &goal);
-    for size in all_sizes {
-        if size > goal {
-            return Some(size);
-        }
-    }
-    None
+pub fn part_one(input: &str) -> Option<u64> {
+    let fs = parse_transcript(input).ok()?;
+    Some(fs.dirs_smaller_than(100000).map(|(_, size)| size).sum())
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let fs = parse_transcript(input).ok()?;
+    fs.dir_to_delete(&Disk::DEVICE).map(|(_, size)| size)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 7);
+    if let Err(e) = parse_transcript(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -234,4 +370,82 @@ mod tests {
         let input = advent_of_code::read_file("examples", 7);
         assert_eq!(part_two(&input), Some(24933642));
     }
+
+    #[test]
+    fn test_transcript() {
+        let input = advent_of_code::read_file("examples", 7);
+        // listing things twice and jumping around by absolute path shouldn't change anything
+        let repeated = format!(
+            "{}\n$ cd /a/e\n$ ls\n584 i\n$ cd ../../d\n$ ls\n4060174 j",
+            input
+        );
+        for transcript in [input.as_str(), repeated.as_str()] {
+            let fs = parse_transcript(transcript).unwrap();
+            let e = fs.resolve(FileSystem::ROOT, "/a/e").unwrap();
+            assert_eq!(fs.path(e), "/a/e");
+            assert_eq!(fs.size(e), 584);
+            assert_eq!(fs.size(FileSystem::ROOT), 48381165);
+            assert_eq!(fs.sizes()[FileSystem::ROOT], 48381165);
+            assert_eq!(fs.resolve(e, "../../d"), fs.resolve(FileSystem::ROOT, "d"));
+            assert_eq!(fs.resolve(FileSystem::ROOT, ".."), Some(FileSystem::ROOT));
+        }
+        assert!(matches!(
+            parse_transcript("$ cd /\n$ cd nope"),
+            Err(TranscriptError::NoSuchDir(2, path)) if path == "nope"
+        ));
+        assert!(matches!(
+            parse_transcript("$ ls\nabc def"),
+            Err(TranscriptError::Malformed(2, _))
+        ));
+    }
+
+    #[test]
+    fn test_tree() {
+        let fs = parse_transcript("$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\ndir e\n29116 f\n$ cd e\n$ ls\n584 i").unwrap();
+        assert_eq!(
+            fs.to_string(),
+            "/\n├── a\n│   ├── e\n│   │   └── i (584)\n│   └── f (29116)\n└── b.txt (14848514)\n"
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = parse_transcript(&input).unwrap();
+        let paths = |dirs: Vec<(DirId, u64)>| {
+            dirs.into_iter()
+                .map(|(id, size)| (fs.path(id), size))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(fs.dirs_smaller_than(100000).collect()),
+            vec![("/a".to_string(), 94853), ("/a/e".to_string(), 584)]
+        );
+        // a bigger disk means less to clean up
+        let roomy = Disk {
+            capacity: 78300000,
+            ..Disk::DEVICE
+        };
+        assert_eq!(
+            paths(fs.dir_to_delete(&roomy).into_iter().collect()),
+            vec![("/a".to_string(), 94853)]
+        );
+        assert_eq!(
+            fs.largest_files(2)
+                .iter()
+                .map(|file| file.size())
+                .collect::<Vec<_>>(),
+            vec![14848514, 8504156]
+        );
+        assert_eq!(
+            fs.files_with_extension("log").collect::<Vec<_>>(),
+            vec![Node::File {
+                dir: fs.resolve(FileSystem::ROOT, "/d").unwrap(),
+                name: "d.log",
+                size: 8033020
+            }]
+        );
+        assert_eq!(fs.walk().count(), 14);
+    }
 }
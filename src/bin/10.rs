@@ -1,74 +1,165 @@
-use ansi_term::Colour::Green;
+use std::fmt;
 
-pub struct Display {
-    pixels: Vec<String>,
+use advent_of_code::helpers::ocr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Addx(i32),
 }
 
-impl Display {
-    pub fn new() -> Self {
-        Self {
-            pixels: vec![String::from(" "); 240],
+// to add an opcode: a variant above, its mnemonic in `parse`, then how long it takes and what it does
+impl Instruction {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split_whitespace();
+        let operand = |arg: Option<&str>| {
+            arg.and_then(|n| n.parse::<i32>().ok())
+                .ok_or_else(|| format!("bad operand in {:?}", line))
+        };
+        match parts.next() {
+            Some("noop") => Ok(Instruction::Noop),
+            Some("addx") => Ok(Instruction::Addx(operand(parts.next())?)),
+            _ => Err(format!("unknown instruction {:?}", line)),
         }
     }
-    pub fn show(&self) {
-        for (idx, s) in self.pixels.iter().enumerate() {
-            print!("{}", s);
-            if (idx + 1) % 40 == 0 {
-                println!("");
-            }
+
+    pub fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Addx(_) => 2, // takes 2 cycles to complete
+        }
+    }
+
+    // applied once all of the instruction's cycles are done
+    fn execute(&self, cpu: &mut Cpu) {
+        match self {
+            Instruction::Noop => {}
+            Instruction::Addx(n) => cpu.x += n,
         }
     }
 }
 
-pub fn build_register_instructions(input: &str) -> Vec<i32> {
+pub fn parse(input: &str) -> Result<Vec<Instruction>, String> {
     input
         .lines()
-        .flat_map(|line| {
-            if line.starts_with("addx ") {
-                vec![0, line.replace("addx ", "").parse::<i32>().unwrap()] // takes 2 cycles to complete
-            } else {
-                vec![0] // noop takes 1 cycle but doesn't change register
-            }
-        })
+        .filter(|line| !line.trim().is_empty())
+        .map(Instruction::parse)
         .collect()
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let register_instructions = build_register_instructions(input);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpu {
+    x: i32,
+    cycle: usize,
+}
 
-    let mut signal_strength = 0i32;
-    let mut register = 1i32;
-    for (idx, x) in register_instructions.iter().enumerate() {
-        // adjust for 0-based indexing
-        if (idx + 1) % 20 == 0 && idx > 0 {
-            if idx + 1 == 20 || ((idx + 1) / 20) % 2 == 1 {
-                signal_strength += register * (idx + 1) as i32;
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cpu {
+    pub fn new() -> Self {
+        Self { x: 1, cycle: 0 }
+    }
+
+    // runs the whole program, calling `observer` with (cycle number, X register) *during* every cycle, cycles count from 1
+    pub fn run(&mut self, program: &[Instruction], mut observer: impl FnMut(usize, i32)) {
+        for instruction in program {
+            for _ in 0..instruction.cycles() {
+                self.cycle += 1;
+                observer(self.cycle, self.x);
             }
+            instruction.execute(self);
         }
-        register += x;
     }
-    Some(signal_strength)
+
+    // the X register during each cycle, trace[0] is cycle 1
+    pub fn trace(program: &[Instruction]) -> Vec<i32> {
+        let mut trace = vec![];
+        Cpu::new().run(program, |_, x| trace.push(x));
+        trace
+    }
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let register_instructions = build_register_instructions(input);
+pub const CRT_WIDTH: usize = 40;
+pub const CRT_HEIGHT: usize = 6;
 
-    let mut d = Display::new();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    pixels: [[bool; CRT_WIDTH]; CRT_HEIGHT],
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    let mut register = 1i32;
-    for (idx, x) in register_instructions.iter().enumerate() {
-        if (idx % 40) as i32 >= register - 1 && (idx % 40) as i32 <= register + 1 {
-            d.pixels[idx] = Green.bold().paint("#").to_string();
+impl Crt {
+    pub fn new() -> Self {
+        Self {
+            pixels: [[false; CRT_WIDTH]; CRT_HEIGHT],
         }
-        register += x;
     }
 
-    d.show();
-    Some(String::from("read the letters above ^^"))
+    // the beam draws one pixel per cycle, lit if the 3 pixel wide sprite centred on X covers it
+    pub fn draw(&mut self, cycle: usize, x: i32) {
+        let idx = (cycle - 1) % (CRT_WIDTH * CRT_HEIGHT);
+        let (row, col) = (idx / CRT_WIDTH, idx % CRT_WIDTH);
+        self.pixels[row][col] = (col as i32 - x).abs() <= 1;
+    }
+
+    pub fn pixels(&self) -> &[[bool; CRT_WIDTH]; CRT_HEIGHT] {
+        &self.pixels
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.pixels.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            let line = row
+                .iter()
+                .map(|lit| if *lit { '#' } else { '.' })
+                .collect::<String>();
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn part_one(input: &str) -> Option<i32> {
+    let program = parse(input).ok()?;
+
+    let mut signal_strength = 0i32;
+    Cpu::new().run(&program, |cycle, x| {
+        // 20th, 60th, 100th... cycles
+        if cycle % 40 == 20 {
+            signal_strength += x * cycle as i32;
+        }
+    });
+    Some(signal_strength)
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    let program = parse(input).ok()?;
+
+    let mut crt = Crt::new();
+    Cpu::new().run(&program, |cycle, x| crt.draw(cycle, x));
+    // if we can't read it, the screen is the answer so a human can
+    Some(ocr::recognise(crt.pixels()).unwrap_or_else(|_| format!("\n{}\n", crt)))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
+    if let Err(e) = parse(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -85,10 +176,37 @@ mod tests {
 
     #[test]
     fn test_part_two() {
+        // the example draws stripes rather than letters, so we get the stripes back
+        let input = advent_of_code::read_file("examples", 10);
+        let screen = part_two(&input).unwrap();
+        assert!(screen.starts_with("\n##..##..##..##..##..##..##..##..##..##..\n"));
+        assert_eq!(screen.lines().count(), 7);
+    }
+
+    #[test]
+    fn test_crt() {
         let input = advent_of_code::read_file("examples", 10);
+        let mut crt = Crt::new();
+        Cpu::new().run(&parse(&input).unwrap(), |cycle, x| crt.draw(cycle, x));
+        assert_eq!(
+            crt.to_string(),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = parse("noop\naddx 3\naddx -5").unwrap();
+        assert_eq!(Cpu::trace(&program), vec![1, 1, 1, 4, 4]);
         assert_eq!(
-            part_two(&input),
-            Some(String::from("read the letters above ^^"))
+            parse("noop\njmp 3"),
+            Err(String::from("unknown instruction \"jmp 3\""))
         );
+        assert_eq!(parse("addx"), Err(String::from("bad operand in \"addx\"")));
     }
 }
@@ -1,284 +1,374 @@
-use std::{borrow::BorrowMut, collections::HashMap};
+use std::ops::{Add, Div, Mul, Rem};
 
-#[derive(Debug, Clone)]
-pub struct Item {
-    worry_level: u128,
+use advent_of_code::helpers::parse::{
+    blocks, comma_list, labelled, parse_all, unsigned, ParseError,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, line_ending, space0},
+    combinator::{value, verify},
+    multi::fold_many0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult, Parser,
+};
+use num_bigint::BigUint;
+
+// the right hand side of "new = old * 19", kept as a tree so it works for any input.
+// No subtraction, worry levels are unsigned and keeping them modulo the lcm doesn't survive it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Num(u128),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
 }
 
-impl Item {
-    pub fn new(worry_level: u128) -> Self {
-        Self { worry_level }
-    }
+// anything we can track worry levels in, `u128` while they're kept small, `BigUint` when they aren't
+pub trait Worry:
+    Clone
+    + PartialEq
+    + From<u128>
+    + Add<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+}
+
+impl<W> Worry for W where
+    W: Clone
+        + PartialEq
+        + From<u128>
+        + Add<Output = W>
+        + Mul<Output = W>
+        + Div<Output = W>
+        + Rem<Output = W>
+{
 }
 
-pub fn items_from(items: Vec<u128>) -> Vec<Item> {
-    let mut result = vec![];
-    for item in items {
-        result.push(Item::new(item));
+impl Expr {
+    pub fn eval<W: Worry>(&self, old: &W) -> W {
+        match self {
+            Expr::Old => old.clone(),
+            Expr::Num(n) => W::from(*n),
+            Expr::Add(a, b) => a.eval(old) + b.eval(old),
+            Expr::Mul(a, b) => a.eval(old) * b.eval(old),
+            Expr::Div(a, b) => a.eval(old) / b.eval(old),
+        }
     }
-    result
 }
 
-#[derive(Debug)]
+// nom nom nom, the usual precedence climbing: expr is terms joined by +, terms are factors joined by * or /
+fn factor(input: &str) -> IResult<&str, Expr> {
+    delimited(
+        space0,
+        alt((
+            value(Expr::Old, tag("old")),
+            unsigned.map(Expr::Num),
+            delimited(char('('), expr, char(')')),
+        )),
+        space0,
+    )(input)
+}
+
+fn term(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = factor(input)?;
+    fold_many0(
+        pair(alt((char('*'), char('/'))), factor),
+        move || first.clone(),
+        |acc, (op, rhs)| match op {
+            '*' => Expr::Mul(Box::new(acc), Box::new(rhs)),
+            _ => Expr::Div(Box::new(acc), Box::new(rhs)),
+        },
+    )(input)
+}
+
+pub fn expr(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = term(input)?;
+    fold_many0(
+        preceded(char('+'), term),
+        move || first.clone(),
+        |acc, rhs| Expr::Add(Box::new(acc), Box::new(rhs)),
+    )(input)
+}
+
+#[derive(Debug, Clone)]
 pub struct Monkey {
-    pub items: Vec<Item>,
-    op: fn(u128) -> u128,
-    test: fn(u128) -> u8,
-    stress_reducer: fn(u128) -> u128,
-    inspected: u128,
+    pub items: Vec<u128>,
+    operation: Expr,
+    divisor: u128,
+    // (monkey to throw to when divisible, when not)
+    targets: (usize, usize),
 }
 
 impl Monkey {
-    pub fn new(items: Vec<Item>, op: fn(u128) -> u128, test: fn(u128) -> u8) -> Self {
-        Self {
-            items,
-            op,
-            test,
-            stress_reducer: |n| n / 3, // for part one, we just use this
-            inspected: 0,
-        }
+    // the "Test: divisible by N" number
+    pub fn divisor(&self) -> u128 {
+        self.divisor
     }
 
-    fn react(&mut self) -> HashMap<u8, Vec<Item>> {
-        let mut dest = HashMap::new();
-        for n in 0..self.items.len() {
-            let item = self.items.get_mut(n).unwrap();
-            self.inspected += 1;
-            // println!(
-            //     "{}",
-            //     Red.paint(format!(
-            //         "\tMonkey inspects item with a worry level of {}.",
-            //         &item.worry_level
-            //     ))
-            // );
-            item.worry_level = (self.op)(item.worry_level);
-            // println!(
-            //     "{}",
-            //     Red.paint(format!("\tnew worry level: {}", &item.worry_level))
-            // );
-            item.worry_level = (self.stress_reducer)(item.worry_level);
-            // println!(
-            //     "{}",
-            //     Blue.paint(format!("\tnew worry level: {}", &item.worry_level))
-            // );
-            let next_monkey = (self.test)(item.worry_level);
-            // println!(
-            //     "{}",
-            //     Red.paint(format!("\tmonkey thows item to monkey: {}", &next_monkey))
-            // );
-            // this is really awkward, probably need to rethink this
-            // basically, since there's nothing special about the Item, we'll just clone it into a map
-            // and then clear this monkey's item vec once we're done
-            dest.entry(next_monkey)
-                .and_modify(|e: &mut Vec<Item>| e.push(Item::new(item.worry_level.clone())))
-                .or_insert(vec![Item::new(item.worry_level.clone())]);
+    fn throw_to<W: Worry>(&self, worry_level: &W) -> usize {
+        if worry_level.clone() % W::from(self.divisor) == W::from(0) {
+            self.targets.0
+        } else {
+            self.targets.1
         }
-        self.items.clear();
-        dest
     }
 }
 
-// TODO quick and dirty for the moment, but perfect time to try out Nom
-// and figure out how to do this in Rust (return a dynamic function from runtime)
-// or maybe something like rhai
-// TODO Just use a build script to preprocess the input ... ?
-fn parse_monkeys(_input: &str) -> Vec<Monkey> {
-    // Monkey 0:
-    //   Starting items: 79, 98
-    //   Operation: new = old * 19
-    //   Test: divisible by 23
-    //     If true: throw to monkey 2
-    //     If false: throw to monkey 3
-
-    // let mut items: Vec<Item>;
-    // let mut op: Option<fn(usize) -> usize>;
-    // let mut test: Option<fn(Item) -> u8>;
-    // let update_re = Regex::new(r"  Operation: new = old (.){1} (\d+)").unwrap();
-
-    // for line in input.lines().into_iter() {
-    //     if line.starts_with("Monkey ") {
-    //         items = vec![];
-    //         op = None;
-    //         test = None;
-    //     } else if line.starts_with("  Operation: new = old * old") {
-    //         op = Some(|x| x * x);
-    //     } else if line.starts_with("  Operation: new = old ") {
-    //         let cap = update_re.captures(line).unwrap();
-    //         op = match cap.get(1).unwrap().as_str() {
-    //             "*" => Some(|x| x * cap.get(2).parse::<usize>()),
-    //             _ => None,
-    //         }
-    //         // this will be an operation followed by either a value or a reference to old
-    //     }
-    // }
-    // there are only 7 monkeys in the input which takes approx 98% less time to hard-code
-    // than I've spent trying to figure out a dynamic approach =(
-    let mut monkeys = vec![];
-
-    monkeys.push(Monkey::new(
-        items_from(vec![99, 67, 92, 61, 83, 64, 98]),
-        |n| n * 17,
-        |worry_level| match worry_level % 3 {
-            0 => 4,
-            _ => 2,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![78, 74, 88, 89, 50]),
-        |n| n * 11,
-        |worry_level| match worry_level % 5 {
-            0 => 3,
-            _ => 5,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![98, 91]),
-        |n| n + 4,
-        |worry_level| match worry_level % 2 {
-            0 => 6,
-            _ => 4,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![59, 72, 94, 91, 79, 88, 94, 51]),
-        |n| n * n,
-        |worry_level| match worry_level % 13 {
-            0 => 0,
-            _ => 5,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![95, 72, 78]),
-        |n| n + 7,
-        |worry_level| match worry_level % 11 {
-            0 => 7,
-            _ => 6,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![76]),
-        |n| n + 8,
-        |worry_level| match worry_level % 17 {
-            0 => 0,
-            _ => 2,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![69, 60, 53, 89, 71, 88]),
-        |n| n + 5,
-        |worry_level| match worry_level % 19 {
-            0 => 7,
-            _ => 1,
-        },
-    ));
-
-    monkeys.push(Monkey::new(
-        items_from(vec![72, 54, 63, 80]),
-        |n| n + 3,
-        |worry_level| match worry_level % 7 {
-            0 => 1,
-            _ => 3,
-        },
-    ));
-    monkeys
+// Monkey 0:
+//   Starting items: 79, 98
+//   Operation: new = old * 19
+//   Test: divisible by 23
+//     If true: throw to monkey 2
+//     If false: throw to monkey 3
+// along with the monkey, its id and the input from its first line so we can point at it if it doesn't add up
+fn monkey(input: &str) -> IResult<&str, (&str, usize, Monkey)> {
+    let start = input;
+    let (input, (id, items, operation, divisor, if_true, if_false)) = tuple((
+        terminated(
+            delimited(tag("Monkey "), unsigned::<usize>, char(':')),
+            line_ending,
+        ),
+        terminated(
+            labelled("Starting items", comma_list(unsigned)),
+            line_ending,
+        ),
+        terminated(
+            labelled("Operation", preceded(tag("new ="), expr)),
+            line_ending,
+        ),
+        terminated(
+            labelled(
+                "Test",
+                preceded(tag("divisible by "), verify(unsigned, |d: &u128| *d > 0)),
+            ),
+            line_ending,
+        ),
+        terminated(
+            labelled("If true", preceded(tag("throw to monkey "), unsigned)),
+            line_ending,
+        ),
+        labelled("If false", preceded(tag("throw to monkey "), unsigned)),
+    ))(input)?;
+    Ok((
+        input,
+        (
+            start,
+            id,
+            Monkey {
+                items,
+                operation,
+                divisor,
+                targets: (if_true, if_false),
+            },
+        ),
+    ))
 }
 
-pub fn part_one(input: &str) -> Option<u128> {
-    let mut monkeys = parse_monkeys(input);
-
-    let num_rounds = 20;
-    // after 20 rounds
-    for _ in 0..num_rounds {
-        // println!("=== Round {} ===", n + 1);
-        for n in 0..monkeys.len() {
-            // println!("{}", Green.paint(format!("Monkey {}'s turn", n)));
-            let stuff_to_toss = monkeys[n].react();
-            for (next_monkey, items) in stuff_to_toss {
-                for item in items {
-                    // println!("sending {:?} to monkey {}", &item, next_monkey);
-                    monkeys[next_monkey as usize].borrow_mut().items.push(item);
-                }
-            }
+// the monkeys have to be numbered 0, 1, 2... and only throw to each other
+pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let specs = parse_all(input, blocks(monkey))?;
+    let count = specs.len();
+    let mut monkeys = vec![];
+    for (n, (start, id, monkey)) in specs.into_iter().enumerate() {
+        if id != n {
+            let message = format!("expected monkey {}, found monkey {}", n, id);
+            return Err(ParseError::at(input, start, message));
         }
-    }
-    // find the 2 most active monkeys
-    let mut most = vec![0; 2];
-    for m in monkeys {
-        if m.inspected > most[0] {
-            most[1] = most[0];
-            most[0] = m.inspected;
-        } else if m.inspected > most[1] {
-            most[1] = m.inspected;
+        let (if_true, if_false) = monkey.targets;
+        if let Some(target) = [if_true, if_false].into_iter().find(|t| *t >= count) {
+            let message = format!(
+                "monkey {} throws to monkey {}, but there are only {} monkeys",
+                id, target, count
+            );
+            return Err(ParseError::at(input, start, message));
         }
+        monkeys.push(monkey);
+    }
+    Ok(monkeys)
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    Some(most[0] * most[1])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorryStrategy {
+    // relief after every inspection, part one uses 3
+    DivideBy(u128),
+    // every test is a divisibility check, so worry levels only matter modulo the LCM of the divisors
+    ModuloLcm,
+    // no relief at all, exact but the numbers grow *very* quickly when a monkey squares them
+    ArbitraryPrecision,
 }
 
-pub fn part_two(input: &str) -> Option<u128> {
-    let mut monkeys = parse_monkeys(input);
+// how many items each monkey inspected, per round
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Inspections {
+    // rounds[round][monkey], round 0 is the first round
+    pub rounds: Vec<Vec<u64>>,
+}
 
-    // we need to change the stress, so we'll use
-    for m in monkeys.iter_mut() {
-        m.stress_reducer = |n| n % (3 * 5 * 2 * 13 * 11 * 17 * 19 * 7)
+impl Inspections {
+    // how many items each monkey inspected over all the rounds
+    pub fn totals(&self) -> Vec<u64> {
+        let monkeys = self.rounds.first().map_or(0, |round| round.len());
+        (0..monkeys)
+            .map(|m| self.rounds.iter().map(|round| round[m]).sum())
+            .collect()
     }
 
-    // even bumping the datatype to u128 only gets us 44 rounds, there has to be a pattern to the inspections
-    let num_rounds = 10_000;
+    // how many items the two most active monkeys inspected, multiplied together
+    pub fn monkey_business(&self) -> u64 {
+        let mut totals = self.totals();
+        totals.sort_unstable_by(|a, b| b.cmp(a));
+        totals.iter().take(2).product()
+    }
+}
 
+fn play<W: Worry>(
+    monkeys: &[Monkey],
+    num_rounds: usize,
+    stress_reducer: impl Fn(W) -> W,
+) -> Inspections {
+    let mut items = monkeys
+        .iter()
+        .map(|m| m.items.iter().map(|n| W::from(*n)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let mut inspections = Inspections::default();
     for _ in 0..num_rounds {
-        // println!("=== Round {} ===", n + 1);
-        for n in 0..monkeys.len() {
-            // println!("{}", Green.paint(format!("Monkey {}'s turn", n)));
-            let stuff_to_toss = monkeys[n].react();
-            for (next_monkey, items) in stuff_to_toss {
-                for item in items {
-                    // println!("sending {:?} to monkey {}", &item, next_monkey);
-                    monkeys[next_monkey as usize].borrow_mut().items.push(item);
-                }
+        let mut round = vec![0; monkeys.len()];
+        for (n, monkey) in monkeys.iter().enumerate() {
+            round[n] = items[n].len() as u64;
+            for worry_level in std::mem::take(&mut items[n]) {
+                let worry_level = stress_reducer(monkey.operation.eval(&worry_level));
+                items[monkey.throw_to(&worry_level)].push(worry_level);
             }
         }
+        inspections.rounds.push(round);
     }
-    // find the 2 most active monkeys
-    let mut most = vec![0; 2];
-    for m in monkeys {
-        if m.inspected > most[0] {
-            most[1] = most[0];
-            most[0] = m.inspected;
-        } else if m.inspected > most[1] {
-            most[1] = m.inspected;
+    inspections
+}
+
+pub fn simulate(monkeys: &[Monkey], num_rounds: usize, strategy: WorryStrategy) -> Inspections {
+    match strategy {
+        WorryStrategy::DivideBy(k) => play(monkeys, num_rounds, |n: u128| n / k),
+        WorryStrategy::ModuloLcm => {
+            let lcm = monkeys
+                .iter()
+                .fold(1, |lcm, m| lcm / gcd(lcm, m.divisor) * m.divisor);
+            play(monkeys, num_rounds, |n: u128| n % lcm)
         }
+        WorryStrategy::ArbitraryPrecision => play(monkeys, num_rounds, |n: BigUint| n),
     }
+}
 
-    Some(most[0] * most[1])
+pub fn part_one(input: &str) -> Option<u64> {
+    let monkeys = parse_monkeys(input).ok()?;
+    Some(simulate(&monkeys, 20, WorryStrategy::DivideBy(3)).monkey_business())
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let monkeys = parse_monkeys(input).ok()?;
+    Some(simulate(&monkeys, 10_000, WorryStrategy::ModuloLcm).monkey_business())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 11);
+    let monkeys = parse_monkeys(input).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // e.g. `cargo solve 11 -- --inspections` prints how busy each monkey was in every round of part two
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--inspections") {
+        let inspections = simulate(&monkeys, 10_000, WorryStrategy::ModuloLcm);
+        for (round, counts) in inspections.rounds.iter().enumerate() {
+            let counts = counts.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            println!("{}\t{}", round + 1, counts.join("\t"));
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_part_one() {
-        let _input = advent_of_code::read_file("examples", 11);
-        // assert_eq!(part_one(&input), None);
-        // Since I hardcoded the monkeys, the example doesn't work
+        let input = advent_of_code::read_file("examples", 11);
+        assert_eq!(part_one(&input), Some(10605));
     }
 
     #[test]
     fn test_part_two() {
-        let _input = advent_of_code::read_file("examples", 11);
-        // assert_eq!(part_two(&input), None);
-        // Since I hardcoded the monkeys, the example doesn't work
+        let input = advent_of_code::read_file("examples", 11);
+        assert_eq!(part_two(&input), Some(2713310158));
+    }
+
+    #[test]
+    fn test_strategies() {
+        let input = advent_of_code::read_file("examples", 11);
+        let monkeys = parse_monkeys(&input).unwrap();
+        assert_eq!(
+            monkeys.iter().map(|m| m.divisor()).collect::<Vec<_>>(),
+            vec![23, 19, 13, 17]
+        );
+
+        // no relief, so these match part two's numbers after the first round and the 20th
+        let exact = simulate(&monkeys, 20, WorryStrategy::ArbitraryPrecision);
+        assert_eq!(exact.rounds[0], vec![2, 4, 3, 6]);
+        assert_eq!(exact.totals(), vec![99, 97, 8, 103]);
+        assert_eq!(simulate(&monkeys, 20, WorryStrategy::ModuloLcm), exact);
+
+        let relieved = simulate(&monkeys, 20, WorryStrategy::DivideBy(3));
+        assert_eq!(relieved.rounds.len(), 20);
+        assert_eq!(relieved.totals(), vec![101, 95, 7, 105]);
+    }
+
+    #[test]
+    fn test_expr() {
+        let eval = |s: &str, old: u128| expr(s).unwrap().1.eval(&old);
+        assert_eq!(eval(" old * 19", 2), 38);
+        assert_eq!(eval(" old * old", 7), 49);
+        assert_eq!(eval("old + 2 * old + 1", 5), 16);
+        assert_eq!(eval("(old + 2) * (old + 1) / 3", 5), 14);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = advent_of_code::read_file("examples", 11);
+        assert!(parse_monkeys(&input.replace("old + 3", "old ^ 3")).is_err());
+        // subtraction could take a worry level below zero
+        assert!(parse_monkeys(&input.replace("old + 3", "old - 3")).is_err());
+        assert_eq!(
+            parse_monkeys(&input.replace("divisible by 23", "divisible by 0"))
+                .unwrap_err()
+                .line,
+            4
+        );
+
+        let err = parse_monkeys(&input.replace("Monkey 1:", "Monkey 5:")).unwrap_err();
+        assert_eq!(err.line, 8);
+        assert_eq!(err.message, "expected monkey 1, found monkey 5");
+
+        let err = parse_monkeys(&input.replacen("throw to monkey 3", "throw to monkey 4", 1))
+            .unwrap_err();
+        assert_eq!(err.line, 1);
+        assert_eq!(
+            err.message,
+            "monkey 0 throws to monkey 4, but there are only 4 monkeys"
+        );
     }
 }
@@ -1,7 +1,9 @@
 use std::collections::BTreeMap;
 
-use advent_of_code::helpers;
-use priq::PriorityQueue;
+use advent_of_code::helpers::{
+    self,
+    search::{self, Graph},
+};
 
 // let's try something different, going to make a directed graph as we parse the grid
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -9,7 +11,6 @@ pub struct Node {
     coord: (usize, usize),
     elevation: char,
     exits: Vec<(usize, usize)>,
-    cost: usize, // since we have to move to a*, we'll use manhattan distance
 }
 
 impl Node {
@@ -18,74 +19,120 @@ impl Node {
             coord,
             elevation,
             exits: vec![],
-            cost: usize::MAX,
         }
     }
 }
 
-pub fn reconstruct_path(
-    came_from: BTreeMap<(usize, usize), (usize, usize)>,
-    current: ((usize, usize), usize),
-    start: (usize, usize),
-) -> Vec<(usize, usize)> {
-    // println!("GOOOOAAAAL!!!");
-    let mut total_path = vec![];
-    let mut curr = current.0;
-    loop {
-        total_path.push(curr);
-        if curr == start {
-            break;
-        }
-        curr = *came_from.get(&curr).unwrap();
+#[derive(Debug)]
+pub struct Heightmap {
+    nodes: BTreeMap<(usize, usize), Node>,
+}
+
+impl Graph for Heightmap {
+    type Node = (usize, usize);
+
+    // every step is one hop, so the cost is always 1
+    fn neighbours(&self, coord: &(usize, usize)) -> Vec<((usize, usize), usize)> {
+        self.nodes
+            .get(coord)
+            .unwrap()
+            .exits
+            .iter()
+            .map(|exit| (*exit, 1))
+            .collect()
     }
-    total_path.reverse();
-    total_path
 }
 
-pub fn a_star(
-    start: (usize, usize),
-    goal: (usize, usize),
-    map: &BTreeMap<(usize, usize), Node>,
-) -> Option<Vec<(usize, usize)>> {
-    let mut open_set = PriorityQueue::new();
-    let mut came_from = BTreeMap::<(usize, usize), (usize, usize)>::new();
-    let mut g_score = BTreeMap::new();
+// the same map with every exit turned around, so we can search backwards from the goal
+struct Reversed<'a>(&'a Heightmap);
+
+impl Graph for Reversed<'_> {
+    type Node = (usize, usize);
+
+    // we can come from any neighbour that has an exit to here
+    fn neighbours(&self, &(x, y): &(usize, usize)) -> Vec<((usize, usize), usize)> {
+        [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ]
+        .into_iter()
+        .filter(|coord| {
+            self.0
+                .nodes
+                .get(coord)
+                .is_some_and(|n| n.exits.contains(&(x, y)))
+        })
+        .map(|coord| (coord, 1))
+        .collect()
+    }
+}
 
-    let start_node = map.get(&start).unwrap();
+impl Heightmap {
+    // since we moved to a*, we'll use manhattan distance to the goal as the heuristic
+    pub fn shortest_path(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Option<Vec<(usize, usize)>> {
+        search::a_star(
+            self,
+            &start,
+            |coord| *coord == goal,
+            |coord| helpers::manhattan_usize(*coord, goal),
+        )
+        .map(|(path, _)| path)
+    }
 
-    open_set.put(start_node.coord, start_node.cost);
-    g_score.insert(start_node.coord.clone(), 0);
+    // one breadth first search backwards from the goal, the first square of `elevation` it reaches is the closest one
+    pub fn shortest_path_from_any(
+        &self,
+        elevation: char,
+        goal: (usize, usize),
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut path = search::bfs(&Reversed(self), &goal, |coord| {
+            self.nodes[coord].elevation == elevation
+        })?;
+        path.reverse();
+        Some(path)
+    }
 
-    loop {
-        if open_set.peek().is_none() {
-            break;
+    // draws the map with the path marked by arrows pointing at the next step, like the puzzle text does
+    pub fn render(&self, path: &[(usize, usize)]) -> String {
+        let mut grid = BTreeMap::new();
+        for (coord, node) in self.nodes.iter() {
+            grid.insert(*coord, node.elevation);
         }
-        let current = open_set.pop().unwrap(); // this removes from the PQ
-        if current.0 == goal {
-            return Some(reconstruct_path(came_from, current, start));
+        for step in path.windows(2) {
+            let ((x, y), (next_x, next_y)) = (step[0], step[1]);
+            let arrow = match (next_x as isize - x as isize, next_y as isize - y as isize) {
+                (0, -1) => '^',
+                (0, 1) => 'v',
+                (-1, 0) => '<',
+                _ => '>',
+            };
+            grid.insert((x, y), arrow);
         }
-
-        for neighbor in &map.get(&current.0).unwrap().exits {
-            let tentative_g_score = g_score.get(&current.0).unwrap() + 1; // this is assuming d(current,neighbor) is one because they're all one hop away
-            if tentative_g_score < *g_score.entry(*neighbor).or_insert(usize::MAX) {
-                // this path to neighbor is better than any previous one, record it
-                came_from.insert(*neighbor, current.0);
-                g_score.insert(*neighbor, tentative_g_score);
-                let neighbor_cost = &map.get(neighbor).unwrap().cost;
-                let f = tentative_g_score + neighbor_cost;
-                if open_set.iter().find(|k| k.0 == *neighbor).is_none() {
-                    open_set.put(*neighbor, f);
-                }
-            }
+        if let Some(goal) = path.last() {
+            grid.insert(*goal, 'E');
         }
+
+        let (max_x, max_y) = self
+            .nodes
+            .keys()
+            .fold((0, 0), |(mx, my), (x, y)| (mx.max(*x), my.max(*y)));
+        (0..=max_y)
+            .map(|y| (0..=max_x).map(|x| grid[&(x, y)]).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
     }
-    None
 }
 
 pub fn parse(
     input: &str,
 ) -> (
-    BTreeMap<(usize, usize), Node>,
+    Heightmap,
     (usize, usize), // start
     (usize, usize), // goal
 ) {
@@ -115,12 +162,10 @@ pub fn parse(
     });
     let goal = goal.unwrap();
 
-    // now that we know where the goal is, we can compute cost (manhattan distance) from each node, as well as track the connected nodes
+    // now that every node exists, we can track the connected nodes
     input.lines().enumerate().for_each(|(y, line)| {
         line.chars().enumerate().for_each(|(x, _)| {
             let mut this_node = h.remove(&(x, y)).unwrap();
-            // update the cost
-            this_node.cost = helpers::manhattan_usize((x, y), (goal.0, goal.1));
             if y > 0 {
                 let n = h.get(&(x, y - 1)).unwrap();
                 if n.elevation as u8 <= this_node.elevation as u8 + 1 {
@@ -148,13 +193,13 @@ pub fn parse(
             h.insert((x, y), this_node);
         });
     });
-    (h, start.unwrap(), goal)
+    (Heightmap { nodes: h }, start.unwrap(), goal)
 }
 
 pub fn part_one(input: &str) -> Option<usize> {
     let (map, start, goal) = parse(input);
 
-    let solution = a_star(start, goal, &map);
+    let solution = map.shortest_path(start, goal);
     match solution {
         Some(p) => {
             // println!("path: {:?}", p);
@@ -170,25 +215,28 @@ pub fn part_one(input: &str) -> Option<usize> {
 pub fn part_two(input: &str) -> Option<usize> {
     // basically same as part 1, but we're trying to discover the best starting point of elevation 'a'
     let (map, _start, goal) = parse(input);
-
-    let min_dist = map
-        .iter()
-        .filter_map(|(k, v)| if v.elevation == 'a' { Some(k) } else { None })
-        .filter_map(|starting_point| {
-            let solution = a_star(*starting_point, goal, &map);
-            match solution {
-                Some(solution) => Some((starting_point, solution.len())),
-                None => None,
-            }
-        })
-        // .collect_vec()
-        .min_by(|a, b| a.1.cmp(&b.1));
-    // println!("{:?}", &min_dist);
-    Some(min_dist.unwrap().1 - 1)
+    map.shortest_path_from_any('a', goal)
+        .map(|path| path.len() - 1)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 12);
+
+    // e.g. `cargo solve 12 -- --render` draws both routes instead
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--render") {
+        let (map, start, goal) = parse(input);
+        for path in [
+            map.shortest_path(start, goal),
+            map.shortest_path_from_any('a', goal),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            println!("{} steps\n{}\n", path.len() - 1, map.render(&path));
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -208,4 +256,22 @@ mod tests {
         let input = advent_of_code::read_file("examples", 12);
         assert_eq!(part_two(&input), Some(29));
     }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::read_file("examples", 12);
+        let (map, start, goal) = parse(&input);
+        let arrows = |rendered: &str| rendered.chars().filter(|c| "^v<>".contains(*c)).count();
+
+        let path = map.shortest_path(start, goal).unwrap();
+        let rendered = map.render(&path);
+        assert_eq!(arrows(&rendered), 31);
+        assert_eq!(rendered.lines().nth(2).unwrap().chars().nth(5), Some('E'));
+
+        // the closest 'a' is in the bottom left corner
+        let path = map.shortest_path_from_any('a', goal).unwrap();
+        assert_eq!(path.first(), Some(&(0, 4)));
+        assert_eq!(path.last(), Some(&goal));
+        assert_eq!(arrows(&map.render(&path)), 29);
+    }
 }
@@ -1,208 +1,124 @@
 use core::fmt;
-use std::cell::RefCell;
 use std::cmp::Ordering;
 
-use itertools::EitherOrBoth::*;
+use advent_of_code::helpers::parse::{blocks, parse_all, unsigned, ParseError};
 use itertools::Itertools;
-
-#[derive(Debug, Clone, PartialEq)]
+use nom::{
+    branch::alt,
+    character::complete::{char, line_ending},
+    combinator::{cut, opt},
+    multi::many0,
+    sequence::{delimited, pair, preceded, separated_pair},
+    IResult, Parser,
+};
+use serde::{Deserialize, Serialize};
+
+// packets happen to be JSON, untagged so a Num is just the number and a List just the array.
+// Equality is structural, so 2 and [[2]] are different packets even though the puzzle can't tell them apart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum Packet {
-    Num(u8),
+    Num(u64),
     List(Vec<Packet>),
-    Tombstone, // this is awkward, but let's get it working before cleaning it up
 }
 
 #[derive(Clone, Debug)]
-pub struct Packets(Vec<Packet>, Vec<Packet>);
+pub struct Packets(Packet, Packet);
 
 impl Packets {
     pub fn in_order(&self) -> bool {
-        match self.ordered() {
-            Ordering::Less => true,
-            _ => false,
-        }
+        self.0.compare(&self.1) == Ordering::Less
     }
+}
 
-    pub fn ordered(&self) -> Ordering {
-        // println!("comparing {:?} and {:?}", self.0, self.1);
-
-        for pair in self.0.iter().zip_longest(self.1.iter()) {
-            // println!("{:?}", pair);
-            match pair {
-                Both(left, right) => {
-                    // println!("comparing {:?} and {:?}", left, right);
-                    match in_order(left, right) {
-                        Ordering::Less => {
-                            return Ordering::Less;
-                        }
-                        Ordering::Greater => {
-                            return Ordering::Greater;
-                        }
-                        Ordering::Equal => {
-                            // println!("couldn't tell, continuing to process rules");
-                        }
-                    }
-                }
-                Left(_) => {
-                    // println!("right ran out of elements, inputs NOT in right order");
-                    return Ordering::Greater;
-                }
-                Right(_) => {
-                    // println!("left ran out of elements, inputs in right order");
-                    return Ordering::Less;
-                }
+impl Packet {
+    // the question/examples are horribly written, but someone on reddit said that it helps to think of the numeric comparison as
+    // alphabetic.  i.e. the 2 in [2, 0] is already greater than the 1 in [1, 9] so the packets are in order and you don't have to compare the rest.
+    // That's how slices compare once a lone number is wrapped in a list, except the wrapping means 2 and [[2]] come out Equal
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Num(left), Packet::Num(right)) => left.cmp(right),
+            (Packet::List(left), Packet::List(right)) => Self::compare_lists(left, right),
+            (Packet::Num(_), Packet::List(right)) => {
+                Self::compare_lists(std::slice::from_ref(self), right)
+            }
+            (Packet::List(left), Packet::Num(_)) => {
+                Self::compare_lists(left, std::slice::from_ref(other))
             }
         }
-        return Ordering::Equal;
     }
-}
 
-fn in_order(left: &Packet, right: &Packet) -> Ordering {
-    match (left, right) {
-        (Packet::Num(left_num), Packet::Num(right_num)) => {
-            // the question/examples are horribly written, but someone on reddit said that it helps to think of the numeric comparison as
-            // alphabetic.  i.e. the 2 in [2, 0] is already greater than the 1 in [1, 9] so the packets are in order and you don't have to compare the rest
-            if left_num == right_num {
-                Ordering::Equal
-            } else {
-                left_num.cmp(right_num)
-            }
-        }
-        (Packet::List(left), Packet::List(right)) => lists_in_order(left, right),
-        (Packet::Num(l), Packet::List(r)) => {
-            // println!("make left a list and call compare on the two");
-            // have to put right back into a list since we matched out of it
-            lists_in_order(
-                &vec![Packet::List(vec![Packet::Num(*l)])],
-                &vec![Packet::List(r.to_vec())],
-            )
-        }
-        (Packet::List(l), Packet::Num(r)) => {
-            // println!("make right a list and call compare on the two");
-            lists_in_order(
-                &vec![Packet::List(l.to_vec())],
-                &vec![Packet::List(vec![Packet::Num(*r)])],
-            )
+    fn compare_lists(left: &[Packet], right: &[Packet]) -> Ordering {
+        left.iter()
+            .zip(right)
+            .map(|(l, r)| l.compare(r))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| left.len().cmp(&right.len()))
+    }
+
+    // breaks the puzzle's ties, a bare number before a list, then the same again all the way down
+    fn compare_shape(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Num(left), Packet::Num(right)) => left.cmp(right),
+            (Packet::List(left), Packet::List(right)) => left
+                .iter()
+                .zip(right)
+                .map(|(l, r)| l.compare_shape(r))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| left.len().cmp(&right.len())),
+            (Packet::Num(_), Packet::List(_)) => Ordering::Less,
+            (Packet::List(_), Packet::Num(_)) => Ordering::Greater,
         }
-        _ => unreachable!(""),
     }
 }
 
-// got rid of the awkward Option(bool) and use Ordering
-fn lists_in_order(left: &Vec<Packet>, right: &Vec<Packet>) -> Ordering {
-    for pair in left.iter().zip_longest(right.iter()) {
-        match pair {
-            Both(left, right) => {
-                // println!("comparing {:?} and {:?}", left, right);
-                let result = in_order(left, right);
-                match result {
-                    Ordering::Equal => {
-                        continue;
-                    }
-                    _ => {
-                        return result;
-                    }
-                }
-            }
-            Left(_) => {
-                // println!("right LIST ran out of elements, inputs NOT in right order");
-                return Ordering::Greater;
-            }
-            Right(_) => {
-                // println!("left LIST ran out of elements, input is in correct order");
-                return Ordering::Less;
-            }
-        }
+// the puzzle's order, with ties between packets like 2 and [[2]] broken so it agrees with ==
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other).then_with(|| self.compare_shape(other))
+    }
+}
+
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
-    Ordering::Equal
 }
 
 impl fmt::Display for Packet {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Packet::Num(n) => write!(f, "{}", n),
-            Packet::List(children) => write!(f, "({})", rec_print(&children)),
-            Packet::Tombstone => Ok(()),
+            Packet::List(children) => write!(f, "[{}]", rec_print(children)),
         }
     }
 }
 
+// a number, or a bracketed list of comma separated packets, once we've seen a comma another packet has to follow
+fn packet(input: &str) -> IResult<&str, Packet> {
+    let list = delimited(
+        char('['),
+        opt(pair(packet, many0(preceded(char(','), cut(packet))))),
+        cut(char(']')),
+    )
+    .map(|items| match items {
+        Some((first, rest)) => std::iter::once(first).chain(rest).collect(),
+        None => vec![],
+    });
+    alt((unsigned.map(Packet::Num), list.map(Packet::List)))(input)
+}
+
 impl Packet {
-    pub fn parse(p: &str) -> Vec<Packet> {
-        let mut stack = vec![];
-        let mut thisvec = RefCell::new(Vec::new());
-
-        let mut iter = p.chars().peekable();
-        loop {
-            match iter.next() {
-                Some(ch) => {
-                    match ch {
-                        '[' => {
-                            stack.push(Packet::Tombstone);
-                        }
-                        '0'..='9' => {
-                            // fml - didn't see that there are tens in the input =/
-                            if ch == '1' {
-                                let next_ch = iter.peek();
-                                if next_ch.is_some() {
-                                    // check if it's a digit and parse the 2-digit num if it is
-                                    let num_to_parse = match *next_ch.unwrap() {
-                                        '0'..='9' => {
-                                            let mut num = String::from("1");
-                                            num.push(iter.next().unwrap());
-                                            num
-                                        }
-                                        _ => String::from("1"),
-                                    };
-                                    stack.push(Packet::Num(num_to_parse.parse::<u8>().unwrap()));
-                                }
-                            } else {
-                                stack.push(Packet::Num(ch.to_string().parse::<u8>().unwrap()));
-                            }
-                        }
-                        ']' => {
-                            // start unwinding until we hit a tombstone, reverse the vec and put it back on the stack
-                            // println!("poppin like it's hot...");
-                            loop {
-                                let val = stack.pop();
-                                if val.is_none() {
-                                    // hit the end of the stack, nothing left
-                                    unreachable!("shouldn't bottom out");
-                                }
-                                let item = val.unwrap();
-                                match item {
-                                    Packet::List(contents) => {
-                                        thisvec.borrow_mut().push(Packet::List(contents));
-                                    }
-                                    Packet::Num(_) => {
-                                        thisvec.borrow_mut().push(item);
-                                    }
-                                    Packet::Tombstone => {
-                                        // see what we have, push back onto the stack as a list
-                                        if stack.is_empty() {
-                                            // we're at the last tombstone, so it's complete
-                                            let mut result = thisvec.get_mut().to_vec();
-                                            result.reverse();
-                                            return result;
-                                        } else {
-                                            // println!("done popping stack, pushing the list we just built up back on");
-                                            let mut tmp = thisvec.get_mut().to_vec();
-                                            tmp.reverse();
-                                            stack.push(Packet::List(tmp));
-                                        }
-                                        thisvec = RefCell::new(Vec::new());
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        ',' => (),
-                        _ => unreachable!("invalid chars in input"),
-                    }
-                }
-                None => (),
-            }
-        }
+    pub fn parse(p: &str) -> Result<Packet, ParseError> {
+        parse_all(p, packet)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("packets are always valid JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Packet, serde_json::Error> {
+        serde_json::from_str(json)
     }
 }
 
@@ -214,50 +130,56 @@ fn rec_print(children: &[Packet]) -> String {
     resp.join(",")
 }
 
-pub fn parse_packets(input: &str) -> Vec<Packets> {
-    input
-        .lines()
-        .chain(vec!["\n\n"])
-        .tuples()
-        .map(|(p1, p2, _whitespace)| Packets(Packet::parse(p1), Packet::parse(p2)))
-        .collect_vec()
+// pairs of packets, one per line, with a blank line between pairs
+pub fn parse_packets(input: &str) -> Result<Vec<Packets>, ParseError> {
+    let pair = separated_pair(packet, line_ending, packet).map(|(p1, p2)| Packets(p1, p2));
+    parse_all(input, blocks(pair))
 }
 
 pub fn part_one(input: &str) -> Option<usize> {
-    let packets = &parse_packets(&input);
+    let packets = parse_packets(input).ok()?;
 
     let answer = packets
         .iter()
         .enumerate()
-        .map(|(idx, pair)| (idx + 1, pair.in_order()))
-        .filter(|m| m.1)
-        .map(|m| m.0)
+        .filter(|(_, pair)| pair.in_order())
+        .map(|(idx, _)| idx + 1)
         .sum();
     Some(answer)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let mut merged = String::from(input);
-    merged.push_str("\n[[2]]\n[[6]]");
-
-    let packets = &mut parse_packets(&merged)
-        .iter()
-        .flat_map(|x| [x.0.clone(), x.1.clone()])
-        .collect::<Vec<Vec<Packet>>>();
-
-    packets.sort_unstable_by(|a, b| Packets(a.clone(), b.clone()).ordered());
-
-    let packet2 = Packet::parse("[[2]]");
-    let packet6 = Packet::parse("[[6]]");
-
-    let idx1 = packets.iter().position(|p| *p == packet2);
-    let idx2 = packets.iter().position(|p| *p == packet6);
-    // zero-based so add 1 to each
-    Some((idx1.unwrap() + 1) * (idx2.unwrap() + 1))
+    let dividers = [Packet::parse("[[2]]").ok()?, Packet::parse("[[6]]").ok()?];
+
+    let packets = parse_packets(input)
+        .ok()?
+        .into_iter()
+        .flat_map(|Packets(p1, p2)| [p1, p2])
+        .chain(dividers.clone())
+        .collect_vec();
+
+    // no need to sort, a divider's index is one more than the number of packets before it.
+    // Anything the puzzle can't tell apart from it, e.g. [2] for [[2]], doesn't count
+    Some(
+        dividers
+            .iter()
+            .map(|divider| {
+                packets
+                    .iter()
+                    .filter(|p| p.compare(divider) == Ordering::Less)
+                    .count()
+                    + 1
+            })
+            .product(),
+    )
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 13);
+    if let Err(e) = parse_packets(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -279,111 +201,271 @@ mod tests {
         assert_eq!(part_two(&input), Some(140));
     }
 
+    #[test]
+    fn test_dividers_with_lookalikes() {
+        // the puzzle can't tell these from the [[2]] divider, so only [1] is before it
+        let input = "[2]\n2\n\n[[[2]]]\n[1]";
+        assert_eq!(part_two(input), Some(2 * 6));
+    }
+
     #[test]
     fn test_packet1() {
-        let p = parse_packets(&String::from("[[1],[2,3,4]]\n[[1],4]"));
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[[1],[2,3,4]]\n[[1],4]")).unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_packet2() {
-        let p = parse_packets(&String::from("[[1],[2,3,4]]\n[[1],4]"));
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[[1],[2,3,4]]\n[[1],4]")).unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_packet3() {
-        let p = parse_packets(&String::from("[9]\n[[8,7,6]]"));
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[9]\n[[8,7,6]]")).unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_packet4() {
-        let p = parse_packets(&String::from("[[4,4],4,4]\n[[4,4],4,4,4]"));
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[[4,4],4,4]\n[[4,4],4,4,4]")).unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_packet5() {
-        let p = parse_packets(&String::from("[7,7,7,7]\n[7,7,7]"));
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[7,7,7,7]\n[7,7,7]")).unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_packet6() {
-        let p = parse_packets(&String::from("[]\n[3]"));
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[]\n[3]")).unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_packet7() {
-        let p = parse_packets(&String::from("[[[]]]\n[[]]"));
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets(&String::from("[[[]]]\n[[]]")).unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_packet8() {
         let p = parse_packets(&String::from(
             "[1,[2,[3,[4,[5,6,7]]]],8,9]\n[1,[2,[3,[4,[5,6,0]]]],8,9]",
-        ));
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        ))
+        .unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet() {
         // got this from reddit
-        let p = parse_packets("[7,7,7]\n[7,7,7,[]]");
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets("[7,7,7]\n[7,7,7,[]]").unwrap();
+        assert!(p[0].in_order());
     }
 
     // [[1],[2,3,4]]\n[[1],2,3,4]
     #[test]
     fn test_rando_packet2() {
         // got this from reddit
-        let p = parse_packets("[[1],[2,3,4]]\n[[1],2,3,4]");
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[1],[2,3,4]]\n[[1],2,3,4]").unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet3() {
         // got this from reddit
-        let p = parse_packets("[[8,[[7]]]]\n[[[[8]]]]");
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[8,[[7]]]]\n[[[[8]]]]").unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet4() {
-        let p = parse_packets("[[[1]],1]\n[[1],2]");
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[[1]],1]\n[[1],2]").unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet5() {
-        let p = parse_packets("[[1],1]\n[[[1]],2]");
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[1],1]\n[[[1]],2]").unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet6() {
-        let p = parse_packets("[[[1]],2]\n[[1],1]");
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[[1]],2]\n[[1],1]").unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_rando_packet7() {
-        let p = parse_packets("[[1],2]\n[[[1]],1]");
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[1],2]\n[[[1]],1]").unwrap();
+        assert!(!p[0].in_order());
     }
 
     #[test]
     fn test_more_rando_packets() {
-        let p = parse_packets("[[8,[[7,10,10,5],[8,4,9]],3,5],[[[3,9,4],5,[7,5,5]],[[3,2,5],[10],[5,5],0,[8]]],[4,2,[],[[7,5,6,3,0],[4,4,10,7],6,[8,10,9]]],[[4,[],4],10,1]]\n[[[[8],[3,10],[7,6,3,7,4],1,8]]]");
-        assert_eq!(true, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[8,[[7,10,10,5],[8,4,9]],3,5],[[[3,9,4],5,[7,5,5]],[[3,2,5],[10],[5,5],0,[8]]],[4,2,[],[[7,5,6,3,0],[4,4,10,7],6,[8,10,9]]],[[4,[],4],10,1]]\n[[[[8],[3,10],[7,6,3,7,4],1,8]]]").unwrap();
+        assert!(p[0].in_order());
     }
 
     #[test]
     fn test_from_results() {
-        let p = parse_packets("[[10]]\n[[3,[],[7,4,8,[]],1]]");
-        assert_eq!(false, p.get(0).unwrap().in_order());
+        let p = parse_packets("[[10]]\n[[3,[],[7,4,8,[]],1]]").unwrap();
+        assert!(!p[0].in_order());
+    }
+
+    #[test]
+    fn test_big_numbers() {
+        let p = parse_packets("[[123],45]\n[[123],100000000000]").unwrap();
+        assert!(p[0].in_order());
+        let (wrapped, bare) = (Packet::parse("[[2]]").unwrap(), Packet::parse("2").unwrap());
+        assert_ne!(wrapped, bare);
+        assert_eq!(wrapped.compare(&bare), Ordering::Equal);
+        assert_eq!(bare.cmp(&wrapped), Ordering::Less);
+        assert!(Packet::parse("[1,[2]").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_packets("[1,2]\n[3,a]").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert!(parse_packets("[1]\n[2]\n[3]").is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let input = advent_of_code::read_file("examples", 13);
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            let packet = Packet::parse(line).unwrap();
+            assert_eq!(packet.to_string(), line);
+            assert_eq!(packet.to_json(), line);
+            assert_eq!(Packet::from_json(line).unwrap().to_string(), line);
+        }
+        // JSON allows whitespace, our puzzle input doesn't have any
+        let spaced = Packet::from_json("[1, [2, []], 3]").unwrap();
+        assert_eq!(spaced.to_string(), "[1,[2,[]],3]");
+        assert!(Packet::from_json("[1,\"2\"]").is_err());
+    }
+
+    // a tiny xorshift so the random packets are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        fn packet(&mut self, depth: u32) -> Packet {
+            if depth == 0 || self.next(3) == 0 {
+                // small numbers so plenty of comparisons go deep before they're decided
+                Packet::Num(self.next(4))
+            } else {
+                Packet::List((0..self.next(4)).map(|_| self.packet(depth - 1)).collect())
+            }
+        }
+    }
+
+    // the puzzle's rules worked straight off the text, nothing shared with Packet, to check it against
+    fn compare_text(left: &str, right: &str) -> Ordering {
+        // the top level items of a list, or None for a number
+        fn items(s: &str) -> Option<Vec<&str>> {
+            let inner = s.strip_prefix('[')?.strip_suffix(']').unwrap();
+            let (mut items, mut depth, mut start) = (vec![], 0, 0);
+            for (i, c) in inner.char_indices() {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    ',' if depth == 0 => {
+                        items.push(&inner[start..i]);
+                        start = i + 1;
+                    }
+                    _ => {}
+                }
+            }
+            if !inner.is_empty() {
+                items.push(&inner[start..]);
+            }
+            Some(items)
+        }
+
+        match (items(left), items(right)) {
+            (None, None) => left.parse::<u64>().unwrap().cmp(&right.parse().unwrap()),
+            (None, Some(_)) => compare_text(&format!("[{}]", left), right),
+            (Some(_), None) => compare_text(left, &format!("[{}]", right)),
+            (Some(l), Some(r)) => {
+                for (a, b) in l.iter().zip(r.iter()) {
+                    match compare_text(a, b) {
+                        Ordering::Equal => continue,
+                        decided => return decided,
+                    }
+                }
+                l.len().cmp(&r.len())
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_packets() {
+        let mut rng = Rng(0x2022_1213);
+        let mut packets = (0..300).map(|_| rng.packet(4)).collect::<Vec<_>>();
+        // plus some the puzzle can't tell apart, which the random ones only turn up now and again
+        for text in ["2", "[2]", "[[2]]", "[[[2]]]", "[]", "[[]]", "[2,[]]"] {
+            packets.push(Packet::parse(text).unwrap());
+        }
+        let text = packets.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+        for (p, text) in packets.iter().zip(text.iter()) {
+            assert_eq!(Packet::parse(text).unwrap().to_string(), *text);
+            assert_eq!(Packet::from_json(&p.to_json()).unwrap(), *p);
+        }
+
+        // the puzzle comparison matches the reference on every pair
+        for (a, a_text) in packets.iter().zip(text.iter()) {
+            for (b, b_text) in packets.iter().zip(text.iter()) {
+                let expected = compare_text(a_text, b_text);
+                assert_eq!(a.compare(b), expected, "{} vs {}", a_text, b_text);
+                assert_eq!(
+                    Packets(a.clone(), b.clone()).in_order(),
+                    expected == Ordering::Less
+                );
+                // and Ord only ever refines it
+                if expected != Ordering::Equal {
+                    assert_eq!(a.cmp(b), expected);
+                }
+            }
+        }
+
+        // Ord is a total order that agrees with ==: antisymmetric and transitive over every triple we can be
+        // bothered checking (the lookalikes are at the end, so include them)
+        let some = packets.iter().rev().take(60).collect::<Vec<_>>();
+        for a in some.iter() {
+            for b in some.iter() {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                if a <= b && b <= a {
+                    assert_eq!(a, b);
+                }
+                for c in some.iter() {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{} <= {} <= {} but not {} <= {}", a, b, c, a, c);
+                    }
+                }
+            }
+        }
+
+        // and sorting agrees with the reference pairwise, not just for neighbours
+        packets.sort();
+        for (i, a) in packets.iter().enumerate() {
+            for b in packets[i..].iter() {
+                assert!(a <= b);
+                assert!(compare_text(&a.to_string(), &b.to_string()).is_le());
+            }
+        }
     }
 }
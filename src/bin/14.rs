@@ -1,174 +1,412 @@
-use itertools::Itertools;
-use regex::Regex;
 use std::cmp;
 use std::fmt::Display;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::thread;
+use std::time::Duration;
 
-const OPEN: &str = " ";
-const SAND: &str = "🐢";
-const WALL: &str = "🧱";
+use advent_of_code::helpers::{ints, IntError};
+use itertools::Itertools;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Point(usize, usize);
 
+// where the sand pours in from
+pub const SOURCE: Point = Point(500, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Air,
+    Rock,
+    Sand,
+}
+
+impl Cell {
+    pub fn symbol(&self) -> char {
+        match self {
+            Cell::Air => '.',
+            Cell::Rock => '#',
+            Cell::Sand => 'o',
+        }
+    }
+}
+
+// what's under the lowest rock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bottom {
+    // sand falls out of the cave forever
+    Abyss,
+    // an endless floor two below the lowest rock
+    Floor,
+}
+
 pub struct Grid {
-    viewport: usize,
-    data: Vec<Vec<String>>,
-    virtual_floor: Option<usize>,
-    virtual_floor_initialized: bool,
-    highest: usize,
+    // x of the first column we store, everything is sized from the rocks and the floor
+    min_x: usize,
+    width: usize,
+    // row by row, down to the floor
+    data: Vec<Cell>,
+    lowest_rock: usize,
+    bottom: Bottom,
 }
 
 impl Grid {
-    pub fn new(size: usize, add_virtual_floor: bool) -> Self {
-        Self {
-            viewport: size,
-            data: vec![vec![String::from(OPEN); size]; size],
-            virtual_floor: if add_virtual_floor { Some(0) } else { None },
-            virtual_floor_initialized: false,
-            highest: 0,
+    pub fn new(lines: &[Vec<Point>], bottom: Bottom) -> Self {
+        let points = lines.iter().flatten();
+        let lowest_rock = points.clone().map(|p| p.1).max().unwrap_or(0);
+        let floor = lowest_rock + 2;
+        // with a floor, sand piles up in a triangle that can't be wider than it is tall, either side of the source
+        // without one, it can only get one column past the rocks before falling away
+        let min_x = points
+            .clone()
+            .map(|p| p.0)
+            .chain([SOURCE.0.saturating_sub(floor)])
+            .min()
+            .unwrap_or(0)
+            .saturating_sub(1);
+        let max_x = points
+            .map(|p| p.0)
+            .chain([SOURCE.0 + floor])
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let width = max_x - min_x + 1;
+
+        let mut grid = Self {
+            min_x,
+            width,
+            data: vec![Cell::Air; width * (floor + 1)],
+            lowest_rock,
+            bottom,
+        };
+        for parts in lines {
+            for (start, end) in parts.iter().tuple_windows() {
+                grid.draw_line(start, end);
+            }
         }
+        grid
+    }
+
+    fn floor(&self) -> usize {
+        self.lowest_rock + 2
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        if self.bottom == Bottom::Floor && y == self.floor() {
+            return Cell::Rock;
+        }
+        self.data[y * self.width + x - self.min_x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.data[y * self.width + x - self.min_x] = cell;
     }
 
     pub fn draw_line(&mut self, start: &Point, end: &Point) {
-        // println!("draw me a line from {:?} to {:?}", start, end);
         if start.0 == end.0 {
             for dy in cmp::min(start.1, end.1)..=(cmp::max(start.1, end.1)) {
-                self.draw_point(start.0, dy);
+                self.set(start.0, dy, Cell::Rock);
             }
         } else {
             for dx in cmp::min(start.0, end.0)..=(cmp::max(start.0, end.0)) {
-                self.draw_point(dx, start.1);
+                self.set(dx, start.1, Cell::Rock);
             }
         }
     }
 
-    pub fn draw_point(&mut self, untranslated_x: usize, untranslated_y: usize) {
-        self.data[untranslated_y][untranslated_x - (500 - (self.viewport / 2))] = WALL.to_string();
-        self.highest = cmp::max(untranslated_y, self.highest);
-    }
-
-    pub fn drop_sand(&mut self) -> Option<Point> {
-        if self.virtual_floor.is_some() && !self.virtual_floor_initialized {
-            // it's too late to think this through, so I'll just create a physical 'virtual' wall!
-            self.draw_line(
-                &Point(500 - (self.viewport / 2), self.highest + 2),
-                &Point(500 + (self.viewport / 2) - 1, self.highest + 2),
-            );
-            self.virtual_floor_initialized = true;
-        }
-        // inject a grain of sand at (500,0) and return the point it stops at.  Why do I need the point? I was going to do some cool animations/stepping
-        let mut x = 500 - (500 - (self.viewport / 2));
-        let mut y = 0;
-
-        if self.data[y][x] == WALL || self.data[y][x] == SAND {
-            println!("All jammed up");
+    // the squares the next grain passes through, from the source to where it comes to rest, None once the
+    // source is blocked or the grain would fall into the abyss
+    pub fn fall(&self) -> Option<Vec<Point>> {
+        let Point(mut x, mut y) = SOURCE;
+        if self.get(x, y) != Cell::Air {
+            // all jammed up
             return None;
         }
+        let mut path = vec![SOURCE];
         loop {
-            if y + 1 == self.viewport {
-                // dropping out of view
+            if self.bottom == Bottom::Abyss && y >= self.lowest_rock {
+                // nothing left to land on
                 return None;
             }
-            if self.data[y + 1][x] == OPEN {
-                // try to move down 1
-                y += 1;
-                continue;
-            } else if self.data[y + 1][x - 1] == OPEN {
-                // try to move down 1, left 1
-                y += 1;
-                x -= 1;
-                continue;
-            } else if self.data[y + 1][x + 1] == OPEN {
-                // try to move down 1, right 1
-                y += 1;
-                x += 1;
-                continue;
+            // try to move down 1, then down 1 left 1, then down 1 right 1
+            match [x, x - 1, x + 1]
+                .into_iter()
+                .find(|next_x| self.get(*next_x, y + 1) == Cell::Air)
+            {
+                Some(next_x) => {
+                    x = next_x;
+                    y += 1;
+                    path.push(Point(x, y));
+                }
+                None => return Some(path),
             }
-            // println!("progress stopped, painting @ [{:?},{:?}]", x, y);
-            self.data[y][x] = SAND.to_string();
-            return Some(Point(x, y));
         }
     }
+
+    // inject a grain of sand at the source and return the point it stops at
+    pub fn drop_sand(&mut self) -> Option<Point> {
+        let rest = *self.fall()?.last()?;
+        self.set(rest.0, rest.1, Cell::Sand);
+        Some(rest)
+    }
+
+    // keeps dropping sand until no more comes to rest, returning how many grains did
+    pub fn fill(&mut self) -> u32 {
+        let mut grains = 0;
+        while self.drop_sand().is_some() {
+            grains += 1;
+        }
+        grains
+    }
+
+    // like `get`, but anywhere at all, everything outside the cave we store is air
+    fn cell_at(&self, x: usize, y: usize) -> Cell {
+        let floor = self.floor();
+        if y > floor || x < self.min_x || x >= self.min_x + self.width {
+            match self.bottom {
+                Bottom::Floor if y == floor => Cell::Rock,
+                _ => Cell::Air,
+            }
+        } else if y == floor && self.bottom == Bottom::Abyss {
+            Cell::Air
+        } else {
+            self.get(x, y)
+        }
+    }
+
+    // draws just what's inside `view`, with the source as '+' and a falling grain if there is one
+    pub fn render(&self, view: &Viewport, grain: Option<&Point>) -> String {
+        (view.top..view.top + view.height)
+            .map(|y| {
+                (view.left..view.left + view.width)
+                    .map(|x| match self.cell_at(x, y) {
+                        _ if grain == Some(&Point(x, y)) => 'o',
+                        Cell::Air if Point(x, y) == SOURCE => '+',
+                        cell => cell.symbol(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// the part of the cave we're looking at, it scrolls to keep up with the sand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Viewport {
+    // starts with the source at the top, in the middle
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            left: SOURCE.0.saturating_sub(width / 2),
+            top: 0,
+            width,
+            height,
+        }
+    }
+
+    // scrolls just far enough to keep `p` a few squares inside the edges
+    pub fn follow(&mut self, p: &Point) {
+        let margin_x = cmp::min(5, self.width / 4);
+        let margin_y = cmp::min(5, self.height / 4);
+        if p.0 < self.left + margin_x {
+            self.left = p.0.saturating_sub(margin_x);
+        } else if p.0 + margin_x >= self.left + self.width {
+            self.left = p.0 + margin_x + 1 - self.width;
+        }
+        if p.1 < self.top + margin_y {
+            self.top = p.1.saturating_sub(margin_y);
+        } else if p.1 + margin_y >= self.top + self.height {
+            self.top = p.1 + margin_y + 1 - self.height;
+        }
+    }
+}
+
+// fills the cave, handing every frame to `frame` as it goes - one per step of every grain's fall, or
+// just one per grain once it's landed. Returns how many grains came to rest.
+pub fn animate(
+    grid: &mut Grid,
+    view: &mut Viewport,
+    per_grain: bool,
+    mut frame: impl FnMut(String) -> io::Result<()>,
+) -> io::Result<u32> {
+    let mut grains = 0;
+    while let Some(path) = grid.fall() {
+        let (rest, falling) = path.split_last().unwrap();
+        if !per_grain {
+            for p in falling {
+                view.follow(p);
+                frame(grid.render(view, Some(p)))?;
+            }
+        }
+        grid.set(rest.0, rest.1, Cell::Sand);
+        grains += 1;
+        view.follow(rest);
+        frame(grid.render(view, None))?;
+    }
+    Ok(grains)
+}
+
+// frames in an exported file are separated by a line with just a form feed on it
+const FRAME_SEPARATOR: &str = "\x0c\n";
+
+// clears the terminal and draws the frame in the top left
+fn show(frame: &str, delay: Duration) {
+    println!("\x1b[2J\x1b[H{}", frame);
+    thread::sleep(delay);
 }
 
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.data {
-            for col in row {
-                f.write_str(&col)?;
-            }
-            f.write_str("\n")?;
+        let last_row = match self.bottom {
+            Bottom::Abyss => self.lowest_rock,
+            Bottom::Floor => self.floor(),
+        };
+        for y in 0..=last_row {
+            let row = (self.min_x..self.min_x + self.width)
+                .map(|x| self.get(x, y).symbol())
+                .collect::<String>();
+            writeln!(f, "{}", row)?;
         }
         Ok(())
     }
 }
 
-pub fn parse_lines(input: &str) -> Vec<Vec<Point>> {
-    let re = Regex::new(r"(\d+,\d+)+( -> )?").unwrap();
+// "498,4 -> 498,6 -> 496,6", one rock path per line
+pub fn parse_lines(input: &str) -> Result<Vec<Vec<Point>>, IntError> {
     let mut lines = vec![];
     for line in input.lines() {
-        let pts = re
-            .captures_iter(line)
-            .filter_map(|c| Some(c.get(1).unwrap().as_str()))
-            .map(|s| {
-                let mut m = s.splitn(2, ",");
-                Point(
-                    m.next().unwrap().parse::<u16>().unwrap().into(),
-                    m.next().unwrap().parse::<u16>().unwrap().into(),
-                )
-            })
+        let pts = ints::<usize>(line)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .tuples()
+            .map(|(x, y)| Point(x, y))
             .collect_vec();
-        lines.push(pts);
+        if !pts.is_empty() {
+            lines.push(pts);
+        }
     }
-    lines
+    Ok(lines)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let lines = parse_lines(input);
-    let mut grid = Grid::new(200, false);
-    for parts in lines {
-        for (n, _) in parts.iter().enumerate() {
-            if n == 0 {
-                continue;
-            }
-            grid.draw_line(&parts[n - 1], &parts[n]);
-        }
-    }
-    let mut grains = 0;
-    while grid.drop_sand().is_some() {
-        grains += 1;
-        // uncomment to step through, <space> + enter, pretty fun to watch
-        // println!("{}", grid);
-        // let pause: String = read!(" {}");
-    }
-    println!("{}", grid);
-    Some(grains)
+    let lines = parse_lines(input).ok()?;
+    let mut grid = Grid::new(&lines, Bottom::Abyss);
+    Some(grid.fill())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let lines = parse_lines(input);
-    let mut grid = Grid::new(500, true);
-    for parts in lines {
-        for (n, _) in parts.iter().enumerate() {
-            if n == 0 {
-                continue;
-            }
-            grid.draw_line(&parts[n - 1], &parts[n]);
+    let lines = parse_lines(input).ok()?;
+    let mut grid = Grid::new(&lines, Bottom::Floor);
+    Some(grid.fill())
+}
+
+struct Args {
+    animate: bool,
+    bottom: Bottom,
+    fps: f64,
+    // (width, height)
+    view: (usize, usize),
+    per_grain: bool,
+    export: Option<String>,
+    replay: Option<String>,
+}
+
+// "80x30"
+fn parse_view(s: &str) -> Result<(usize, usize), String> {
+    s.split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| format!("expected a size like 80x30, got {:?}", s))
+}
+
+// anything we can turn into a delay between frames, so not 0, negative, NaN or so slow it overflows a Duration
+fn parse_fps(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|fps| fps.is_finite() && *fps > 0.0)
+        .filter(|fps| Duration::try_from_secs_f64(1.0 / fps).is_ok())
+        .ok_or_else(|| format!("expected a frame rate above 0, got {:?}", s))
+}
+
+// e.g. `cargo solve 14 -- --animate --part 2 --fps 60 --view 120x40`
+// `--per-grain` draws a frame per grain instead of per step, `--export frames.txt` writes the frames out
+// instead of playing them and `--replay frames.txt` plays them back
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        animate: args.contains("--animate"),
+        bottom: match args.opt_value_from_str::<_, u8>("--part")? {
+            Some(2) => Bottom::Floor,
+            _ => Bottom::Abyss,
+        },
+        fps: args.opt_value_from_fn("--fps", parse_fps)?.unwrap_or(30.0),
+        view: args
+            .opt_value_from_fn("--view", parse_view)?
+            .unwrap_or((80, 30)),
+        per_grain: args.contains("--per-grain"),
+        export: args.opt_value_from_str("--export")?,
+        replay: args.opt_value_from_str("--replay")?,
+    })
+}
+
+fn run_animation(lines: &[Vec<Point>], args: &Args) -> io::Result<()> {
+    let delay = Duration::from_secs_f64(1.0 / args.fps);
+    if let Some(path) = &args.replay {
+        let frames = fs::read_to_string(path)?;
+        for frame in frames
+            .split(FRAME_SEPARATOR)
+            .filter(|f| !f.trim().is_empty())
+        {
+            show(frame.trim_end(), delay);
         }
+        return Ok(());
     }
-    let mut grains = 0;
-    while grid.drop_sand().is_some() {
-        grains += 1;
-        // uncomment to step through, <space> + enter, pretty fun to watch
-        // println!("{}", grid);
-        // let pause: String = read!(" {}");
-    }
-    // println!("{}", grid);
-    Some(grains)
+
+    let mut grid = Grid::new(lines, args.bottom);
+    let mut view = Viewport::new(args.view.0, args.view.1);
+    let grains = match &args.export {
+        Some(path) => {
+            let mut out = BufWriter::new(fs::File::create(path)?);
+            animate(&mut grid, &mut view, args.per_grain, |frame| {
+                write!(out, "{}\n{}", frame, FRAME_SEPARATOR)
+            })?
+        }
+        None => animate(&mut grid, &mut view, args.per_grain, |frame| {
+            show(&frame, delay);
+            Ok(())
+        })?,
+    };
+    println!("{} grains of sand came to rest", grains);
+    Ok(())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let lines = parse_lines(input).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if args.animate || args.replay.is_some() {
+        if let Err(e) = run_animation(&lines, &args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -188,4 +426,82 @@ mod tests {
         let input = advent_of_code::read_file("examples", 14);
         assert_eq!(part_two(&input), Some(93));
     }
+
+    #[test]
+    fn test_wide_floor() {
+        // the pile is far wider than the rocks, 302 rows of sand less the 2 the rock takes up
+        let mut grid = Grid::new(&parse_lines("500,300 -> 501,300").unwrap(), Bottom::Floor);
+        assert_eq!(grid.fill(), 302 * 302 - 2);
+        assert_eq!(grid.get(199, 301), Cell::Sand);
+        assert_eq!(grid.get(198, 301), Cell::Air);
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::read_file("examples", 14);
+        let mut grid = Grid::new(&parse_lines(&input).unwrap(), Bottom::Abyss);
+        grid.fill();
+        let view = Viewport {
+            left: 494,
+            top: 0,
+            width: 10,
+            height: 10,
+        };
+        assert_eq!(
+            grid.render(&view, None),
+            "......+...
+..........
+......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########."
+        );
+    }
+
+    #[test]
+    fn test_animate() {
+        let input = advent_of_code::read_file("examples", 14);
+        let mut frames = vec![];
+        let mut grid = Grid::new(&parse_lines(&input).unwrap(), Bottom::Floor);
+        let mut view = Viewport::new(12, 8);
+        let grains = animate(&mut grid, &mut view, true, |frame| {
+            frames.push(frame);
+            Ok(())
+        });
+        assert_eq!(grains.unwrap(), 93);
+        assert_eq!(frames.len(), 93);
+        // the pile grows wider than the view, so it had to scroll to keep up, and the last grain blocks the source
+        assert_ne!(view, Viewport::new(12, 8));
+        assert!(frames.iter().all(|frame| frame.lines().count() == 8));
+
+        // otherwise every step of every fall gets a frame, the first grain falls 8 squares onto the rock
+        let mut grid = Grid::new(&parse_lines(&input).unwrap(), Bottom::Abyss);
+        assert_eq!(grid.fall().unwrap().len(), 9);
+        let mut steps = 0;
+        while let Some(path) = grid.fall() {
+            steps += path.len();
+            grid.drop_sand();
+        }
+        let mut frames = 0;
+        let mut grid = Grid::new(&parse_lines(&input).unwrap(), Bottom::Abyss);
+        animate(&mut grid, &mut Viewport::new(12, 8), false, |_| {
+            frames += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(frames, steps);
+    }
+
+    #[test]
+    fn test_parse_fps() {
+        assert_eq!(parse_fps("60"), Ok(60.0));
+        assert_eq!(parse_fps("0.5"), Ok(0.5));
+        for bad in ["0", "-5", "NaN", "inf", "1e-300", "fast"] {
+            assert!(parse_fps(bad).is_err(), "{}", bad);
+        }
+    }
 }
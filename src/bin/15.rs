@@ -1,7 +1,7 @@
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 
-use advent_of_code::helpers;
-use itertools::Itertools;
+use advent_of_code::helpers::{self, IntervalSet};
 use nom::bytes::complete::tag;
 use nom::character::complete::{self, line_ending};
 use nom::combinator::opt;
@@ -57,48 +57,98 @@ pub fn parse(input: &str) -> IResult<&str, Vec<(Sensor, Beacon)>> {
     many1(sensor_beacon_pair)(input)
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let (_, locations) = parse(input).unwrap();
-    // println!("{:?}", locations);
-
-    let mut target_y: i32 = 0;
-    TARGET_ROW.with(|target_row| {
-        target_y = *target_row.borrow();
-    });
-
-    // which sensors have a range that overlaps the target row?
-    let distances = locations
+fn sensor_distances(locations: &[(Sensor, Beacon)]) -> Vec<(&Sensor, i64)> {
+    locations
         .iter()
         .map(|(s, b)| {
             (
                 s,
-                helpers::manhattan((s.x as i64, s.y as i64), (b.x as i64, b.y as i64)),
+                helpers::manhattan((s.x as i64, s.y as i64), (b.x as i64, b.y as i64)) as i64,
             )
         })
+        .collect()
+}
+
+// the x positions covered by any sensor on row y, as a set of merged ranges
+fn row_coverage(sensor_dists: &[(&Sensor, i64)], y: i64) -> IntervalSet {
+    sensor_dists
+        .iter()
         .filter_map(|(s, dist)| {
-            if ((s.y - dist as i32)..(s.y + dist as i32)).contains(&(target_y as i32)) {
-                Some((s, dist))
+            let effective_distance = dist - (s.y as i64 - y).abs();
+            if effective_distance >= 0 {
+                Some(s.x as i64 - effective_distance..s.x as i64 + effective_distance + 1)
             } else {
                 None
             }
         })
-        .collect::<Vec<(&Sensor, usize)>>();
+        .collect()
+}
 
-    // println!("Sensors that reach target row: {:?}", distances);
+pub fn part_one(input: &str) -> Option<u64> {
+    let (_, locations) = parse(input).unwrap();
+
+    let mut target_y: i32 = 0;
+    TARGET_ROW.with(|target_row| {
+        target_y = *target_row.borrow();
+    });
+
+    let mut covered = row_coverage(&sensor_distances(&locations), target_y as i64);
 
-    // how many spaces are projected onto that row?
-    let x_pos = distances
+    // a beacon sitting on the row is, by definition, a place where a beacon *can* be
+    locations
         .iter()
-        .map(|(sensor, dist)| {
-            let distance_to_line = sensor.y - target_y;
-            let distance_on_line = *dist as i32 - distance_to_line.abs();
-            sensor.x - distance_on_line..sensor.x + distance_on_line
-        })
-        .flatten()
-        .unique()
-        .collect_vec();
+        .filter(|(_, b)| b.y == target_y)
+        .for_each(|(_, b)| covered.remove(b.x as i64..b.x as i64 + 1));
 
-    Some(x_pos.len())
+    Some(covered.len())
+}
+
+// the lone spot must sit just outside several sensors' ranges, so it's where the lines running along the
+// outside of their diamonds cross - or where one of them crosses the edge of the search area
+fn candidates(sensor_dists: &[(&Sensor, i64)], max_dim: i64) -> BTreeSet<(i64, i64)> {
+    // every edge is a diagonal, so it's enough to know where it crosses x = 0
+    // rising lines y = x + c, falling lines y = c - x
+    let mut rising = BTreeSet::new();
+    let mut falling = BTreeSet::new();
+    for (s, dist) in sensor_dists {
+        let (x, y, r) = (s.x as i64, s.y as i64, dist + 1);
+        rising.insert(y - x - r);
+        rising.insert(y - x + r);
+        falling.insert(y + x - r);
+        falling.insert(y + x + r);
+    }
+    let rising = rising.iter().map(|c| ((0, *c), (1, c + 1)));
+    let falling = falling.iter().map(|c| ((0, *c), (1, c - 1)));
+    let edges = [
+        ((0, 0), (1, 0)),
+        ((0, max_dim), (1, max_dim)),
+        ((0, 0), (0, 1)),
+        ((max_dim, 0), (max_dim, 1)),
+    ];
+
+    let mut points = BTreeSet::from([(0, 0), (0, max_dim), (max_dim, 0), (max_dim, max_dim)]);
+    for line in rising.clone() {
+        points.extend(
+            falling
+                .clone()
+                .chain(edges)
+                .filter_map(|other| helpers::intersection(line, other)),
+        );
+    }
+    for line in falling {
+        points.extend(
+            edges
+                .iter()
+                .filter_map(|edge| helpers::intersection(line, *edge)),
+        );
+    }
+    points
+}
+
+fn in_range(sensor_dists: &[(&Sensor, i64)], (x, y): (i64, i64)) -> bool {
+    sensor_dists
+        .iter()
+        .any(|(s, dist)| helpers::manhattan((s.x as i64, s.y as i64), (x, y)) as i64 <= *dist)
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
@@ -108,46 +158,16 @@ pub fn part_two(input: &str) -> Option<u64> {
     MAX_DIM.with(|dim| {
         max_dim = *dim.borrow();
     });
+    let max_dim = max_dim as i64;
 
-    let sensor_dists = locations
-        .iter()
-        .map(|(s, b)| {
-            (
-                s,
-                helpers::manhattan((s.x as i64, s.y as i64), (b.x as i64, b.y as i64)),
-            )
-        })
-        .collect::<Vec<(&Sensor, usize)>>();
-
-    for y in 0..=max_dim {
-        // 4 million times is still fast enough
-        let mut sensor_ranges = sensor_dists
-            .iter()
-            .filter_map(|(s, dist)| {
-                let distance_to_row = (s.y - y).abs() as i32;
-                if distance_to_row <= *dist as i32 {
-                    let effective_distance = *dist as i32 - distance_to_row;
-                    Some(s.x - effective_distance..s.x + effective_distance + 1)
-                } else {
-                    None
-                }
-            })
-            .collect_vec();
-        sensor_ranges.sort_by(|a, b| a.start.cmp(&b.start));
-
-        let mut x = 0;
-        // iterate over the sensors and see if we have a point that's outside all of them.  each time we process a sensor, we can bump x by the sensor distance calculation at that y coord
-        for range in sensor_ranges {
-            if range.contains(&x) {
-                x = range.end;
-            }
-        }
-        if x <= max_dim {
-            println!("{:?} is the missing beacon", (x, y));
-            return Some(x as u64 * 4_000_000 + y as u64);
-        }
-    }
-    None
+    let sensor_dists = sensor_distances(&locations);
+
+    // crossings can land between squares (or off the map), so every candidate gets checked against the sensors
+    candidates(&sensor_dists, max_dim)
+        .into_iter()
+        .filter(|(x, y)| (0..=max_dim).contains(x) && (0..=max_dim).contains(y))
+        .find(|p| !in_range(&sensor_dists, *p))
+        .map(|(x, y)| x as u64 * 4_000_000 + y as u64)
 }
 
 fn main() {
@@ -177,4 +197,28 @@ mod tests {
         let input = advent_of_code::read_file("examples", 15);
         assert_eq!(part_two(&input), Some(56000011));
     }
+
+    #[test]
+    fn test_candidates_match_row_scan() {
+        let input = advent_of_code::read_file("examples", 15);
+        let (_, locations) = parse(&input).unwrap();
+        let sensor_dists = sensor_distances(&locations);
+
+        // the old way, scanning every row for a gap
+        let scanned = (0..=20).find_map(|y| {
+            row_coverage(&sensor_dists, y)
+                .gaps(0..21)
+                .next()
+                .map(|gap| (gap.start, y))
+        });
+        assert_eq!(scanned, Some((14, 11)));
+        assert!(candidates(&sensor_dists, 20).contains(&(14, 11)));
+        assert!(!in_range(&sensor_dists, (14, 11)));
+
+        // a spot in the corner isn't between any two diamonds
+        let corner = [(Sensor { x: 10, y: 10 }, Beacon { x: 10, y: 0 })];
+        let sensor_dists = sensor_distances(&corner);
+        assert!(!in_range(&sensor_dists, (0, 0)));
+        assert!(candidates(&sensor_dists, 20).contains(&(0, 0)));
+    }
 }
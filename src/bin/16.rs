@@ -1,14 +1,12 @@
-use std::{
-    cmp,
-    collections::{BTreeMap, HashMap},
-};
+use std::collections::BTreeMap;
 
+use advent_of_code::helpers::search::{self, Graph};
+use itertools::Itertools;
 use regex::Regex;
 
 #[derive(Debug)]
 pub struct Valve {
-    flow: i64,
-    mask: i64,
+    flow: u32,
     tunnels: Vec<String>,
 }
 
@@ -18,141 +16,363 @@ pub fn parse(input: &str) -> Cave {
     let re = Regex::new(r"Valve ([A-Z][A-Z]) has flow rate=(\d+); tunnels? leads? to valves? (.+)")
         .unwrap();
     let mut cave = BTreeMap::new();
-    let mut n = 0u32;
     input.lines().for_each(|line| {
         let cap = re.captures(line).expect("unmatched line for regex");
         let name = cap[1].to_string();
-        let flow = cap[2].parse::<i64>().expect("flow rate wrong");
+        let flow = cap[2].parse::<u32>().expect("flow rate wrong");
         let tunnels: Vec<String> = cap[3]
             .to_string()
             .split(", ")
             .map(|s| s.to_string())
             .collect();
-        cave.insert(
-            name,
-            Valve {
-                flow,
-                mask: i64::pow(2, n),
-                tunnels,
-            },
-        );
-        n += 1;
+        cave.insert(name, Valve { flow, tunnels });
     });
     cave
 }
 
-fn calculate_distances(cave: &Cave) -> BTreeMap<(String, String), i64> {
-    let mut distances = BTreeMap::new();
-    cave.keys().for_each(|x| {
-        cave.keys().for_each(|y| {
-            // we'll generate all the values, e.g. AA -> AA to make the rest easier
-            println!("building route from {} to {}", x, y);
-            let dist = if cave.get(x).unwrap().tunnels.contains(y) {
-                1
-            } else {
-                i64::MAX
-            };
-            distances.entry((x.clone(), y.clone())).or_insert(dist);
-        });
-    });
-    cave.keys().for_each(|k| {
-        cave.keys().for_each(|i| {
-            cave.keys().for_each(|j| {
-                println!("{} to {}", &i, &j);
-                let ij = &*distances.get(&(i.clone(), j.clone())).unwrap();
-                println!("{} to {}", &i, &k);
-                let ik = &*distances.get(&(i.clone(), k.clone())).unwrap();
-                println!("{} to {}", &k, &j);
-                let kj = &*distances.get(&(k.clone(), j.clone())).unwrap();
-                println!("now checking for next hop: {}, {}, {}", ij, ik, kj);
-                let tmp = if *ik == i64::MAX || *kj == i64::MAX {
-                    i64::MAX
-                } else {
-                    cmp::max(*ij, *ik + *kj)
-                };
-                distances.insert((i.clone(), j.clone()), tmp);
-            });
-        });
-    });
+// the tunnels between valves, every hop takes a minute
+struct Tunnels<'a>(&'a Cave);
+
+impl Graph for Tunnels<'_> {
+    type Node = String;
+
+    fn neighbours(&self, valve: &String) -> Vec<(String, usize)> {
+        self.0
+            .get(valve)
+            .unwrap()
+            .tunnels
+            .iter()
+            .map(|t| (t.clone(), 1))
+            .collect()
+    }
+}
+
+// where we start, its valve never has any flow
+const START: &str = "AA";
+
+// we only ever walk to a valve to open it, so the rest are just corridors. The valves with some flow are numbered
+// from 0 so each gets a bit in a mask, and the start goes after them
+pub struct Valves {
+    names: Vec<String>,
+    flows: Vec<u32>,
+    // minutes to walk between any two of them, by number
+    distances: Vec<Vec<u32>>,
+}
+
+impl Valves {
+    pub fn new(cave: &Cave) -> Self {
+        let names = cave
+            .iter()
+            .filter(|(_, valve)| valve.flow > 0)
+            .map(|(name, _)| name.clone())
+            .chain([START.to_string()])
+            .collect::<Vec<_>>();
+        let paths = search::all_pairs_shortest_paths(&Tunnels(cave), &names);
+        // a valve we can't get to is as far away as it gets
+        let distances = names
+            .iter()
+            .map(|from| {
+                names
+                    .iter()
+                    .map(|to| {
+                        paths
+                            .get(&(from.clone(), to.clone()))
+                            .map_or(u32::MAX, |dist| *dist as u32)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            flows: names[..names.len() - 1]
+                .iter()
+                .map(|name| cave[name].flow)
+                .collect(),
+            names,
+            distances,
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.flows.len()
+    }
+}
 
-    distances
+// a valve we walk to and open, with the minutes left once it's open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    valve: usize,
+    remaining: u32,
 }
 
+// walks every order of opening valves we have time for, remembering the most pressure for each set of opened
+// valves (the mask in `state`, which is also where it goes in `acc`). Just the pressure, the order only matters
+// for the plans we end up picking so `plan` works those out again afterwards
 pub fn visit(
-    valve: String,
-    budget: i64,
-    state: i64,
-    cave: &Cave,
-    distances: &BTreeMap<(String, String), i64>,
-    flow: i64,
-    acc: &mut HashMap<i64, i64>,
+    valves: &Valves,
+    valve: usize,
+    budget: u32,
+    state: usize,
+    pressure: u32,
+    acc: &mut [u32],
 ) {
-    let n = *acc.entry(state).or_default();
-    acc.insert(state, cmp::max(n, flow));
-    for k in cave.iter().filter(|(_, cv)| cv.flow > 0).map(|(ck, _)| ck) {
-        let dist = distances.get(&(valve.clone(), k.to_string())).unwrap();
-        let new_budget = budget - dist - 1;
-        let mask = cave.get(k).unwrap().mask;
-        if state & mask != 0 || new_budget < 0 {
+    acc[state] = acc[state].max(pressure);
+    for (next, flow) in valves.flows.iter().enumerate() {
+        let mask = 1 << next;
+        // walking there and opening it takes a minute more than the walk, no point opening a valve with no time
+        // left for it to do anything
+        let remaining = budget
+            .saturating_sub(valves.distances[valve][next])
+            .saturating_sub(1);
+        if state & mask != 0 || remaining == 0 {
             continue;
+        }
+        visit(
+            valves,
+            next,
+            remaining,
+            state | mask,
+            // once it's open, it releases its flow for every minute we have left
+            pressure + remaining * flow,
+            acc,
+        );
+    }
+}
+
+// the most pressure for each set of valves, by mask. Sets we can't open in time just get 0. Real inputs only
+// have 15 valves with flow, so that's 32768 of them
+fn best_pressures(valves: &Valves, budget: u32) -> Vec<u32> {
+    let mut acc = vec![0; 1 << valves.flows.len()];
+    visit(valves, valves.start(), budget, 0, 0, &mut acc);
+    acc
+}
+
+// the set with the most pressure on its own
+fn best_set(acc: &[u32]) -> usize {
+    (0..acc.len()).max_by_key(|mask| acc[*mask]).unwrap_or(0)
+}
+
+// the elephant and I should never open the same valve, so pair up the best sets that don't overlap, as (mine,
+// the elephant's). Rather than try every pair, first find the best set inside each set - then each of my sets just
+// pairs with the best one in the valves I leave
+fn best_pair(acc: &[u32]) -> Option<(usize, usize)> {
+    let all = acc.len() - 1;
+    // a set's best is either itself or the best in one of the sets with a valve taken out, and those are all
+    // smaller so already done
+    let mut best_within = (0..acc.len()).collect::<Vec<_>>();
+    for mask in 0..acc.len() {
+        let mut bits = mask;
+        while bits != 0 {
+            let without = best_within[mask & !(1 << bits.trailing_zeros())];
+            if acc[without] > acc[best_within[mask]] {
+                best_within[mask] = without;
+            }
+            bits &= bits - 1;
+        }
+    }
+    (0..acc.len())
+        .map(|mine| (mine, best_within[all ^ mine]))
+        .max_by_key(|(mine, elephants)| acc[*mine] + acc[*elephants])
+}
+
+// finds an order to open exactly the valves in `mask` that releases `pressure`, which `visit` told us is possible
+fn retrace(
+    valves: &Valves,
+    valve: usize,
+    budget: u32,
+    mask: usize,
+    pressure: u32,
+    path: &mut Vec<Step>,
+) -> bool {
+    if mask == 0 {
+        return pressure == 0;
+    }
+    for (next, flow) in valves.flows.iter().enumerate() {
+        let remaining = budget
+            .saturating_sub(valves.distances[valve][next])
+            .saturating_sub(1);
+        if mask & 1 << next == 0 || remaining == 0 || remaining * flow > pressure {
+            continue;
+        }
+        path.push(Step {
+            valve: next,
+            remaining,
+        });
+        if retrace(
+            valves,
+            next,
+            remaining,
+            mask & !(1 << next),
+            pressure - remaining * flow,
+            path,
+        ) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+// the steps for a set `best_pressures` picked out, only searching the orders of the valves in it
+fn plan(valves: &Valves, budget: u32, acc: &[u32], mask: usize) -> Vec<Step> {
+    let mut steps = vec![];
+    retrace(valves, valves.start(), budget, mask, acc[mask], &mut steps);
+    steps
+}
+
+// what everyone does each minute, plus how much pressure is being released as we go
+pub fn explain(valves: &Valves, budget: u32, plans: &[(&str, &[Step])]) -> String {
+    // (minute it's opened in, valve) for every agent's plan
+    let opened = plans
+        .iter()
+        .flat_map(|(_, steps)| steps.iter())
+        .map(|step| (budget - step.remaining, step.valve))
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![];
+    let mut released = 0;
+    for minute in 1..=budget {
+        lines.push(format!("== Minute {} ==", minute));
+        let open = opened
+            .iter()
+            .filter(|(opened_in, _)| *opened_in < minute)
+            .map(|(_, valve)| *valve)
+            .sorted_by_key(|valve| &valves.names[*valve])
+            .collect::<Vec<_>>();
+        if open.is_empty() {
+            lines.push("No valves are open.".to_string());
         } else {
-            let flow_to_here = cave.get(k).unwrap().flow;
-            visit(
-                k.clone(),
-                new_budget,
-                state | mask,
-                cave,
-                distances,
-                flow + new_budget + flow_to_here,
-                acc,
-            );
+            let pressure = open.iter().map(|valve| valves.flows[*valve]).sum::<u32>();
+            released += pressure;
+            lines.push(format!(
+                "Valves {} are open, releasing {} pressure ({} so far).",
+                open.iter().map(|valve| &valves.names[*valve]).join(", "),
+                pressure,
+                released
+            ));
         }
+
+        for (who, steps) in plans {
+            // everyone spends the minutes between opening one valve and the next walking to it
+            let mut previous = 0;
+            let mut action = format!("{}: wait", who);
+            for step in steps.iter() {
+                let opened_in = budget - step.remaining;
+                if minute == opened_in {
+                    action = format!("{}: open valve {}", who, valves.names[step.valve]);
+                } else if minute > previous && minute < opened_in {
+                    action = format!("{}: move towards valve {}", who, valves.names[step.valve]);
+                }
+                previous = opened_in;
+            }
+            lines.push(action);
+        }
+        lines.push(String::new());
     }
+    lines.push(format!("Total pressure released: {}", released));
+    lines.join("\n")
 }
 
-pub fn part_one(input: &str) -> Option<i64> {
-    let cave = parse(input);
-    let distances = calculate_distances(&cave);
-    dbg!(&distances);
-    let mut acc: HashMap<i64, i64> = HashMap::new();
-    let state = 0i64;
-    visit(
-        String::from("AA"),
-        30i64,
-        state,
-        &cave,
-        &distances,
-        0,
-        &mut acc,
-    );
-    dbg!(cave, &acc);
-    // Some(*acc.values().max().unwrap())
-    // Yeah, this doesn't work yet
-    None
+pub fn part_one(input: &str) -> Option<u32> {
+    let valves = Valves::new(&parse(input));
+    best_pressures(&valves, 30).into_iter().max()
 }
 
-pub fn part_two(_input: &str) -> Option<u32> {
-    None
+pub fn part_two(input: &str) -> Option<u32> {
+    let valves = Valves::new(&parse(input));
+    // 4 minutes teaching the elephant, then we each take a set of valves
+    let acc = best_pressures(&valves, 26);
+    best_pair(&acc).map(|(mine, elephants)| acc[mine] + acc[elephants])
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 16);
+
+    // e.g. `cargo solve 16 -- --explain` prints the best plan for each part, minute by minute
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--explain") {
+        let valves = Valves::new(&parse(input));
+        let acc = best_pressures(&valves, 30);
+        let mine = plan(&valves, 30, &acc, best_set(&acc));
+        println!("{}\n", explain(&valves, 30, &[("You", &mine[..])]));
+        let acc = best_pressures(&valves, 26);
+        if let Some((mine, elephants)) = best_pair(&acc) {
+            let mine = plan(&valves, 26, &acc, mine);
+            let elephants = plan(&valves, 26, &acc, elephants);
+            println!(
+                "{}",
+                explain(
+                    &valves,
+                    26,
+                    &[("You", &mine[..]), ("The elephant", &elephants[..])]
+                )
+            );
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_part_one() {
-        let _input = advent_of_code::read_file("examples", 16);
-        // assert_eq!(part_one(&input), Some(1651));
+        let input = advent_of_code::read_file("examples", 16);
+        assert_eq!(part_one(&input), Some(1651));
     }
 
     #[test]
     fn test_part_two() {
-        let _input = advent_of_code::read_file("examples", 16);
-        // assert_eq!(part_two(&input), None);
+        let input = advent_of_code::read_file("examples", 16);
+        assert_eq!(part_two(&input), Some(1707));
+    }
+
+    #[test]
+    fn test_collapsed_distances() {
+        let input = advent_of_code::read_file("examples", 16);
+        let valves = Valves::new(&parse(&input));
+        // the 6 valves with some flow then AA, every way round
+        assert_eq!(valves.names, ["BB", "CC", "DD", "EE", "HH", "JJ", "AA"]);
+        assert_eq!(valves.flows, [13, 2, 20, 3, 22, 21]);
+        assert!(valves.distances.iter().all(|row| row.len() == 7));
+        assert_eq!(valves.distances[valves.start()][4], 5);
+        assert_eq!(valves.distances[4][valves.start()], 5);
+        assert_eq!(valves.distances[2][2], 0);
+    }
+
+    #[test]
+    fn test_explain() {
+        let input = advent_of_code::read_file("examples", 16);
+        let valves = Valves::new(&parse(&input));
+
+        let acc = best_pressures(&valves, 30);
+        let mine = plan(&valves, 30, &acc, best_set(&acc));
+        let steps = mine
+            .iter()
+            .map(|s| valves.names[s.valve].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec!["DD", "BB", "JJ", "HH", "EE", "CC"]);
+        let explained = explain(&valves, 30, &[("You", &mine[..])]);
+        assert!(explained.starts_with(
+            "== Minute 1 ==\nNo valves are open.\nYou: move towards valve DD\n\n== Minute 2 ==\nNo valves are open.\nYou: open valve DD\n"
+        ));
+        assert!(explained.contains(
+            "== Minute 5 ==\nValves DD are open, releasing 20 pressure (60 so far).\nYou: open valve BB\n"
+        ));
+        assert!(explained.ends_with("You: wait\n\nTotal pressure released: 1651"));
+
+        let acc = best_pressures(&valves, 26);
+        let (mine, elephants) = best_pair(&acc).unwrap();
+        assert_eq!(mine & elephants, 0);
+        let mine = plan(&valves, 26, &acc, mine);
+        let elephants = plan(&valves, 26, &acc, elephants);
+        let explained = explain(
+            &valves,
+            26,
+            &[("You", &mine[..]), ("The elephant", &elephants[..])],
+        );
+        assert!(explained.ends_with("Total pressure released: 1707"));
     }
 }
@@ -1,13 +1,15 @@
-use std::collections::BTreeMap;
+use std::fs;
 
+use advent_of_code::helpers::{cycle, parse::parse_all};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{self, line_ending},
+    combinator::verify,
     multi::{many1, separated_list1},
     IResult, Parser,
 };
-const GRID_HASH_LENGTH: u128 = 300;
+const GRID_HASH_LENGTH: usize = 300;
 
 // TETRIS!
 const ROCKS: &str = "####
@@ -34,32 +36,55 @@ pub enum Rock {
     Space,
 }
 
+// a row of the chamber fits in a byte, with bit x set when column x (from the left) is rock
+const MAX_WIDTH: u8 = 8;
+
+// the shape of the chamber and where new rocks appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub width: u8,
+    // gap between the left wall and a new rock
+    pub spawn_x: u8,
+    // empty rows between the top of the tower and the bottom of a new rock
+    pub spawn_gap: usize,
+}
+
+// the puzzle's chamber: 7 wide, rocks appear two in from the left wall and three rows above the tower
+pub const PUZZLE_RULES: Rules = Rules {
+    width: 7,
+    spawn_x: 2,
+    spawn_gap: 3,
+};
+
 #[derive(Debug)]
 pub struct Shape {
-    rocks: Vec<Vec<Rock>>,
-    offsets: Vec<(u128, u128)>,
+    // bottom row first, in the same bit layout as the chamber's rows with the shape against the left wall
+    rows: Vec<u8>,
+    width: u8,
 }
 
 impl Shape {
-    pub fn new(offsets: Vec<(u128, u128)>, rocks: Vec<Vec<Rock>>) -> Self {
-        Self { rocks, offsets }
-    }
-    pub fn height(&self) -> u128 {
-        self.rocks.len() as u128
-    }
-    pub fn max_width(&self) -> u128 {
-        self.rocks
+    // rows as drawn, top row first
+    pub fn new(rocks: Vec<Vec<Rock>>) -> Self {
+        let rows = rocks
             .iter()
+            .rev()
             .map(|row| {
                 row.iter()
-                    .filter(|rock| match rock {
-                        Rock::Rock => true,
-                        Rock::Space => false,
-                    })
-                    .count()
+                    .enumerate()
+                    .filter(|(_, rock)| **rock == Rock::Rock)
+                    .fold(0u8, |mask, (x, _)| mask | 1 << x)
             })
+            .collect::<Vec<_>>();
+        let width = rows
+            .iter()
+            .map(|row| 8 - row.leading_zeros() as u8)
             .max()
-            .unwrap() as u128
+            .unwrap_or(0);
+        Self { rows, width }
+    }
+    pub fn height(&self) -> u128 {
+        self.rows.len() as u128
     }
 }
 
@@ -69,31 +94,21 @@ pub enum Move {
     Right,
 }
 
+// rows wider than the chamber could ever be won't fit in a byte, so they don't parse
 pub fn shapes(input: &str) -> IResult<&str, Vec<Shape>> {
     separated_list1(
         tag("\n\n"),
         separated_list1(
             line_ending,
-            many1(alt((
-                complete::char('#').map(|_| Rock::Rock),
-                complete::char('.').map(|_| Rock::Space),
-            ))),
+            verify(
+                many1(alt((
+                    complete::char('#').map(|_| Rock::Rock),
+                    complete::char('.').map(|_| Rock::Space),
+                ))),
+                |row: &Vec<Rock>| row.len() <= MAX_WIDTH as usize,
+            ),
         )
-        .map(|rocks| {
-            Shape::new(
-                rocks
-                    .iter()
-                    .enumerate()
-                    .flat_map(|(y, row)| {
-                        row.iter().enumerate().filter_map(move |(x, r)| match r {
-                            Rock::Rock => Some((x as u128, y as u128)),
-                            Rock::Space => None,
-                        })
-                    })
-                    .collect::<Vec<(u128, u128)>>(),
-                rocks,
-            )
-        }),
+        .map(Shape::new),
     )(input)
 }
 
@@ -105,186 +120,231 @@ pub fn moves(input: &str) -> IResult<&str, Vec<Move>> {
 }
 
 pub struct Grid {
-    data: BTreeMap<(u128, u128), Rock>,
+    // one bitmask per row, bottom row first, there's an implied floor under row 0
+    rows: Vec<u8>,
 }
 
 impl Grid {
-    fn can_place_shape(&self, this_shape: &Shape, desired: (u128, u128)) -> bool {
-        !this_shape
-            .offsets
+    // true if the shape, with its bottom left corner at (x, y), doesn't hit anything
+    fn can_place_shape(&self, this_shape: &Shape, x: u8, y: usize) -> bool {
+        this_shape
+            .rows
             .iter()
-            .any(|(x, y)| self.data.get(&(desired.0 + x, desired.1 - y)).is_some())
+            .zip(self.rows.iter().skip(y))
+            .all(|(shape_row, row)| shape_row << x & row == 0)
     }
-    fn max_y(&self) -> u128 {
-        *self.data.keys().map(|(_, y)| y).max().unwrap_or(&0)
+    fn place_shape(&mut self, this_shape: &Shape, x: u8, y: usize) {
+        let top = y + this_shape.rows.len();
+        if self.rows.len() < top {
+            self.rows.resize(top, 0);
+        }
+        for (row, shape_row) in self.rows[y..top].iter_mut().zip(this_shape.rows.iter()) {
+            *row |= shape_row << x;
+        }
     }
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+    // the top few hundred rows of the tower, rocks never get anywhere near that far down so they stand in for all of it
     fn snapshot(&self) -> Vec<u8> {
-        let mut snap = vec![];
-        // ugh
-        for y in 0..GRID_HASH_LENGTH {
-            for x in 0..7 {
-                match self.data.get(&(x, y)) {
-                    Some(r) => {
-                        if *r == Rock::Rock {
-                            snap.push(1);
-                        } else {
-                            snap.push(0);
-                        }
-                    }
-                    None => {
-                        snap.push(0);
-                    }
-                };
-            }
-        }
-        snap
+        self.rows[self.rows.len().saturating_sub(GRID_HASH_LENGTH)..].to_vec()
     }
 }
 
-// modifying the response to indicate if we found a cycle or not (repeat, height)
-pub fn play_sorta_tetris(
-    shapes: Vec<Shape>,
-    moves: Vec<Move>,
-    limit: u128,
-    detect_cycle_only: bool,
-) -> (Option<u128>, Option<(u128, u128)>) {
-    // we'll just keep repeating the same moves and shapes
-    let mut moves = moves.iter().enumerate().cycle();
-    let mut shapes = shapes.iter().enumerate().cycle();
-
-    let mut grid = Grid {
-        data: BTreeMap::new(),
-    };
+// everything needed to keep dropping rocks one at a time
+pub struct Chamber<'a> {
+    grid: Grid,
+    rules: Rules,
+    shapes: &'a [Shape],
+    moves: &'a [Move],
+    // index of the next shape and move to use, we'll just keep repeating the same moves and shapes
+    shape_idx: usize,
+    move_idx: usize,
+}
 
-    // insert a floor to make easier
-    for x in 0..7 {
-        grid.data.insert((x, 0), Rock::Rock);
+impl<'a> Chamber<'a> {
+    // every shape has to fit where it appears, and the chamber has to fit in our rows
+    pub fn new(shapes: &'a [Shape], moves: &'a [Move], rules: Rules) -> Result<Self, String> {
+        if rules.width == 0 || rules.width > MAX_WIDTH {
+            return Err(format!(
+                "chamber width {} isn't between 1 and {}",
+                rules.width, MAX_WIDTH
+            ));
+        }
+        if shapes.is_empty() || moves.is_empty() {
+            return Err("need at least one shape and one move".to_string());
+        }
+        if let Some(n) = shapes.iter().position(|shape| {
+            shape.width == 0
+                || rules
+                    .spawn_x
+                    .checked_add(shape.width)
+                    .filter(|right| *right <= rules.width)
+                    .is_none()
+        }) {
+            return Err(format!(
+                "shape {} doesn't fit in a chamber {} wide when it appears {} from the wall",
+                n + 1,
+                rules.width,
+                rules.spawn_x
+            ));
+        }
+        Ok(Self {
+            grid: Grid { rows: vec![] },
+            rules,
+            shapes,
+            moves,
+            shape_idx: 0,
+            move_idx: 0,
+        })
     }
 
-    let mut rocks_stopped = 0;
+    pub fn height(&self) -> u128 {
+        self.grid.height() as u128
+    }
 
-    // (last n lines of grid maybe, shape index, move index)
-    let mut cache: BTreeMap<(Vec<u8>, usize, usize), Vec<(u128, u128)>> = BTreeMap::new();
+    // (last n lines of grid, shape index, move index) is enough to know the tower will repeat
+    pub fn state_key(&self) -> (Vec<u8>, usize, usize) {
+        (self.grid.snapshot(), self.shape_idx, self.move_idx)
+    }
 
-    while rocks_stopped != limit {
-        let this_shape = shapes.next().unwrap();
-        let start_pos = &grid.max_y();
-        let mut curr_pos: (u128, u128) = (2, start_pos + 3 + this_shape.1.height());
+    pub fn drop_rock(&mut self) {
+        let this_shape = &self.shapes[self.shape_idx];
+        self.shape_idx = (self.shape_idx + 1) % self.shapes.len();
+        let (mut x, mut y) = (
+            self.rules.spawn_x,
+            self.grid.height() + self.rules.spawn_gap,
+        );
 
-        let mut last_move_idx;
         loop {
-            let this_move = moves.next().unwrap();
-            last_move_idx = this_move.0;
-            let current_position = match this_move.1 {
-                // we can move left all the way to 0 since we're keeping track of shape at 0,0 - but we have to account for the width of the shape when moving right
-                Move::Left => {
-                    if let Some(x_pos) = curr_pos.0.checked_sub(1) {
-                        let desired = (x_pos, curr_pos.1);
-                        if grid.can_place_shape(this_shape.1, desired) {
-                            desired
-                        } else {
-                            curr_pos
-                        }
-                    } else {
-                        curr_pos
-                    }
-                }
-                Move::Right => {
-                    let desired = (curr_pos.0 + 1, curr_pos.1);
-                    if curr_pos.0 == 7 - this_shape.1.max_width()
-                        || !grid.can_place_shape(this_shape.1, desired)
-                    {
-                        curr_pos
-                    } else {
-                        desired
-                    }
-                }
+            let this_move = &self.moves[self.move_idx];
+            self.move_idx = (self.move_idx + 1) % self.moves.len();
+            let desired = match this_move {
+                Move::Left => x.checked_sub(1),
+                // we have to account for the width of the shape when moving right
+                Move::Right => Some(x + 1).filter(|x| x + this_shape.width <= self.rules.width),
             };
-            // try to drop it down
-            let desired_next_position = (current_position.0, current_position.1 - 1);
-            if grid.can_place_shape(this_shape.1, desired_next_position) {
-                // set next position
-                curr_pos = desired_next_position;
-            } else {
-                // stick shape at current pos
-                for position in this_shape.1.offsets.iter() {
-                    grid.data.insert(
-                        (
-                            position.0 + current_position.0,
-                            current_position.1 - position.1,
-                        ),
-                        Rock::Rock,
-                    );
+            if let Some(desired) = desired {
+                if self.grid.can_place_shape(this_shape, desired, y) {
+                    x = desired;
                 }
-                rocks_stopped += 1;
-                break;
             }
-        }
-        // we successfully placed a block, we're looking back n lines of grid
-        // (last n lines of grid, shape index, move index) => (cycle_length, grid_height)
-        if curr_pos.1 > GRID_HASH_LENGTH && detect_cycle_only {
-            // how do we build up something meaningful for the state of the grid?
-            let snap = grid.snapshot();
-            let key = (snap, this_shape.0, last_move_idx);
-            let entry = cache
-                .entry(key.clone())
-                .and_modify(|v| v.push((rocks_stopped, grid.max_y())))
-                .or_insert(vec![(rocks_stopped, grid.max_y())]);
-            if entry.len() > 2 {
-                // println!("We've seen this state before! {:?} => {:?}", key, entry);
-                let second = entry.get(1).unwrap();
-                let first = entry.get(0).unwrap();
-                let cycle_length = second.0 - first.0;
-                let tower_height = second.1 - first.1;
-                return (None, Some((cycle_length, tower_height)));
+            // try to drop it down, or stick shape at current pos
+            if y > 0 && self.grid.can_place_shape(this_shape, x, y - 1) {
+                y -= 1;
+            } else {
+                self.grid.place_shape(this_shape, x, y);
+                return;
             }
         }
     }
+}
 
-    (Some(*grid.data.keys().map(|(_, y)| y).max().unwrap()), None)
+// drops rocks until the tower starts repeating itself, then works out the height after `limit` rocks
+pub fn play_sorta_tetris(
+    shapes: &[Shape],
+    moves: &[Move],
+    rules: Rules,
+    limit: u128,
+) -> Result<u128, String> {
+    Ok(cycle::extrapolate(
+        Chamber::new(shapes, moves, rules)?,
+        limit as u64,
+        Chamber::drop_rock,
+        Chamber::state_key,
+        |chamber| chamber.height() as i64,
+    ) as u128)
 }
 
-pub fn part_one(input: &str) -> Option<u128> {
-    let (_, shapes1) = shapes(ROCKS).unwrap();
-    let (_, moves1) = moves(input).unwrap();
+// a shape set like `ROCKS`, the shapes drawn with '#' and '.' and separated by blank lines
+pub fn load_shapes(path: &str) -> Result<Vec<Shape>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_all(&text, shapes).map_err(|e| format!("{}: {}", path, e))
+}
 
-    let limit = 2022;
+fn solve(input: &str, shapes: &[Shape], rules: Rules, limit: u128) -> Result<u128, String> {
+    let moves = parse_all(input, moves).map_err(|e| e.to_string())?;
+    play_sorta_tetris(shapes, &moves, rules, limit)
+}
 
-    // by making the cycle detection keep track of enough of the board to detect the cycle in part 2, we made it require too much data for part one so it fails to detect a cycle
-    // luckily, just letting it play out takes <50ms in release mode for part 1
-    let (tower_height, _) = play_sorta_tetris(shapes1, moves1, limit, false);
-    tower_height
+pub fn part_one(input: &str) -> Option<u128> {
+    let (_, shapes) = shapes(ROCKS).unwrap();
+    solve(input, &shapes, PUZZLE_RULES, 2022).ok()
 }
 
 pub fn part_two(input: &str) -> Option<u128> {
-    let (_, shapes1) = shapes(ROCKS).unwrap();
-    let (_, moves1) = moves(input).unwrap();
-
-    let limit = 1_000_000_000_000;
-
-    // first, detect the cycle
-    let (_, cycle) = play_sorta_tetris(shapes1, moves1, limit, true);
-    let (cycle_length, cycle_height) = cycle.unwrap();
-
-    let complete_cycles = limit / cycle_length;
-    let incomplete_cycles = limit % cycle_length;
-
-    println!("Detected a cycle, every {cycle_length} shapes the tower will grow {cycle_height}.  This is {complete_cycles} complete cycles, with {incomplete_cycles} to play out...");
-
-    // then, play out the remaining rounds
-    // meh just reread rather than fix the borrowing
     let (_, shapes) = shapes(ROCKS).unwrap();
-    let (_, moves) = moves(input).unwrap();
+    solve(input, &shapes, PUZZLE_RULES, 1_000_000_000_000).ok()
+}
 
-    let (played_height, _) = play_sorta_tetris(shapes, moves, incomplete_cycles, false);
+struct Args {
+    shapes: Option<String>,
+    rules: Rules,
+    rocks: Option<u128>,
+}
 
-    Some(played_height.unwrap() + cycle_height * complete_cycles)
+// e.g. `cargo solve 17 -- --shapes my_rocks.txt --width 8 --spawn-x 3 --spawn-gap 4 --rocks 5000`
+// any of them can be left out to use the puzzle's
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        shapes: args.opt_value_from_str("--shapes")?,
+        rules: Rules {
+            width: args
+                .opt_value_from_str("--width")?
+                .unwrap_or(PUZZLE_RULES.width),
+            spawn_x: args
+                .opt_value_from_str("--spawn-x")?
+                .unwrap_or(PUZZLE_RULES.spawn_x),
+            spawn_gap: args
+                .opt_value_from_str("--spawn-gap")?
+                .unwrap_or(PUZZLE_RULES.spawn_gap),
+        },
+        rocks: args.opt_value_from_str("--rocks")?,
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 17);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    if let Err(e) = parse_all(input, moves) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.shapes.is_none() && args.rules == PUZZLE_RULES && args.rocks.is_none() {
+        advent_of_code::solve!(1, part_one, input);
+        advent_of_code::solve!(2, part_two, input);
+        return;
+    }
+
+    // what if...
+    let shapes = match &args.shapes {
+        Some(path) => load_shapes(path),
+        None => Ok(shapes(ROCKS).unwrap().1),
+    };
+    let shapes = shapes.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    for limit in args
+        .rocks
+        .map_or(vec![2022, 1_000_000_000_000], |n| vec![n])
+    {
+        match solve(input, &shapes, args.rules, limit) {
+            Ok(height) => println!("{} rocks: {} tall", limit, height),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -302,4 +362,142 @@ mod tests {
         let input = advent_of_code::read_file("examples", 17);
         assert_eq!(part_two(&input), Some(1514285714288));
     }
+
+    #[test]
+    fn test_shape_masks() {
+        let (_, shapes) = shapes(ROCKS).unwrap();
+        let masks = shapes
+            .iter()
+            .map(|s| (s.rows.clone(), s.width))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            masks,
+            vec![
+                (vec![0b1111], 4),
+                (vec![0b010, 0b111, 0b010], 3),
+                // the L is drawn upside down, bottom row first
+                (vec![0b111, 0b100, 0b100], 3),
+                (vec![0b1, 0b1, 0b1, 0b1], 1),
+                (vec![0b11, 0b11], 2),
+            ]
+        );
+
+        let input = advent_of_code::read_file("examples", 17);
+        let (_, moves) = moves(&input).unwrap();
+        let mut chamber = Chamber::new(&shapes, &moves, PUZZLE_RULES).unwrap();
+        chamber.drop_rock();
+        chamber.drop_rock();
+        // ..####. then the plus on top of it, one in from the left
+        assert_eq!(
+            chamber.grid.rows,
+            vec![0b0111100, 0b0001000, 0b0011100, 0b0001000]
+        );
+    }
+
+    #[test]
+    fn test_rules_and_custom_shapes() {
+        let input = advent_of_code::read_file("examples", 17);
+        let (_, moves) = moves(&input).unwrap();
+
+        // bars as wide as the chamber can only stack straight up
+        let (_, bars) = shapes("####").unwrap();
+        let rules = Rules {
+            width: 4,
+            spawn_x: 0,
+            spawn_gap: 3,
+        };
+        assert_eq!(
+            play_sorta_tetris(&bars, &moves, rules, 1_000_000),
+            Ok(1_000_000)
+        );
+
+        // single blocks in a chamber one wide do the same, the gap above the tower doesn't matter
+        let (_, blocks) = shapes("#").unwrap();
+        let rules = Rules {
+            width: 1,
+            spawn_x: 0,
+            spawn_gap: 10,
+        };
+        assert_eq!(play_sorta_tetris(&blocks, &moves, rules, 2022), Ok(2022));
+
+        // a wider chamber gives the rocks more room to spread out, so the tower is shorter
+        let (_, rocks) = shapes(ROCKS).unwrap();
+        let wider = Rules {
+            width: 8,
+            ..PUZZLE_RULES
+        };
+        assert!(play_sorta_tetris(&rocks, &moves, wider, 2022).unwrap() < 3068);
+
+        let offset = Rules {
+            spawn_x: 4,
+            ..PUZZLE_RULES
+        };
+        assert_eq!(
+            play_sorta_tetris(&rocks, &moves, offset, 2022),
+            Err(
+                "shape 1 doesn't fit in a chamber 7 wide when it appears 4 from the wall"
+                    .to_string()
+            )
+        );
+        let too_wide = Rules {
+            width: 9,
+            ..PUZZLE_RULES
+        };
+        assert!(play_sorta_tetris(&rocks, &moves, too_wide, 2022).is_err());
+        // far enough out that working out the right edge would overflow
+        let far_out = Rules {
+            spawn_x: u8::MAX,
+            ..PUZZLE_RULES
+        };
+        assert_eq!(
+            play_sorta_tetris(&rocks, &moves, far_out, 2022),
+            Err(
+                "shape 1 doesn't fit in a chamber 7 wide when it appears 255 from the wall"
+                    .to_string()
+            )
+        );
+
+        // the jets have to parse too
+        assert!(solve(&input, &rocks, PUZZLE_RULES, 2022).is_ok());
+        let err = solve("<<>x>", &rocks, PUZZLE_RULES, 2022).unwrap_err();
+        assert!(err.ends_with("<<>x>\n   ^"));
+    }
+
+    #[test]
+    fn test_load_shapes() {
+        let shapes = load_shapes("src/examples/17-shapes.txt").unwrap();
+        let masks = shapes
+            .iter()
+            .map(|s| (s.rows.clone(), s.width))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            masks,
+            vec![
+                (vec![0b010, 0b111], 3),
+                (vec![0b011, 0b110], 3),
+                (vec![0b1111], 4)
+            ]
+        );
+        let input = advent_of_code::read_file("examples", 17);
+        let (_, moves) = moves(&input).unwrap();
+        assert!(play_sorta_tetris(&shapes, &moves, PUZZLE_RULES, 2022).is_ok());
+
+        // the error says which file, and points at the bad cell
+        assert_eq!(
+            load_shapes("src/examples/17-bad-shapes.txt").unwrap_err(),
+            "src/examples/17-bad-shapes.txt: unexpected trailing input at line 5, column 2\n#x.\n ^"
+        );
+        assert!(load_shapes("src/examples/17-empty-shapes.txt")
+            .unwrap_err()
+            .starts_with("src/examples/17-empty-shapes.txt: "));
+        assert!(load_shapes("src/examples/17-missing-shapes.txt").is_err());
+
+        // a row has to fit in a byte
+        let (_, widest) = super::shapes("#......#").unwrap();
+        assert_eq!(widest[0].width, MAX_WIDTH);
+        assert!(parse_all("#\n\n.#......#", super::shapes).is_err());
+        assert!(load_shapes("src/examples/17-wide-shapes.txt")
+            .unwrap_err()
+            .contains("at line 3, column 1\n.#......#\n^"));
+    }
 }
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use advent_of_code::helpers::{
+    parse::{parse_all, signed, ParseError},
+    search::{self, Graph},
+};
+use nom::{
+    character::complete::{char, line_ending},
+    multi::separated_list1,
+    sequence::tuple,
+    IResult,
+};
+
+pub type Cube = (i32, i32, i32);
+
+fn cube(input: &str) -> IResult<&str, Cube> {
+    let (input, (x, _, y, _, z)) = tuple((signed, char(','), signed, char(','), signed))(input)?;
+    Ok((input, (x, y, z)))
+}
+
+// one "x,y,z" per line
+pub fn parse(input: &str) -> Result<Vec<Cube>, ParseError> {
+    parse_all(input, separated_list1(line_ending, cube))
+}
+
+fn neighbours(&(x, y, z): &Cube) -> [Cube; 6] {
+    [
+        (x - 1, y, z),
+        (x + 1, y, z),
+        (x, y - 1, z),
+        (x, y + 1, z),
+        (x, y, z - 1),
+        (x, y, z + 1),
+    ]
+}
+
+// the air around the droplet, in a box one bigger than it on every side so the steam can get all the way round
+struct Steam {
+    lava: HashSet<Cube>,
+    min: Cube,
+    max: Cube,
+}
+
+impl Steam {
+    fn new(lava: HashSet<Cube>) -> Self {
+        let min = lava.iter().fold((i32::MAX, i32::MAX, i32::MAX), |m, c| {
+            (m.0.min(c.0 - 1), m.1.min(c.1 - 1), m.2.min(c.2 - 1))
+        });
+        let max = lava.iter().fold((i32::MIN, i32::MIN, i32::MIN), |m, c| {
+            (m.0.max(c.0 + 1), m.1.max(c.1 + 1), m.2.max(c.2 + 1))
+        });
+        Self { lava, min, max }
+    }
+
+    fn in_bounds(&self, c: &Cube) -> bool {
+        (self.min.0..=self.max.0).contains(&c.0)
+            && (self.min.1..=self.max.1).contains(&c.1)
+            && (self.min.2..=self.max.2).contains(&c.2)
+    }
+}
+
+impl Graph for Steam {
+    type Node = Cube;
+
+    fn neighbours(&self, cube: &Cube) -> Vec<(Cube, usize)> {
+        neighbours(cube)
+            .into_iter()
+            .filter(|c| self.in_bounds(c) && !self.lava.contains(c))
+            .map(|c| (c, 1))
+            .collect()
+    }
+}
+
+pub fn part_one(input: &str) -> Option<usize> {
+    let cubes = parse(input).ok()?;
+    let lava = cubes.iter().copied().collect::<HashSet<_>>();
+    // every face that isn't up against another cube
+    Some(
+        lava.iter()
+            .flat_map(neighbours)
+            .filter(|c| !lava.contains(c))
+            .count(),
+    )
+}
+
+pub fn part_two(input: &str) -> Option<usize> {
+    let cubes = parse(input).ok()?;
+    let steam = Steam::new(cubes.into_iter().collect());
+    // flood the box with steam from a corner, air pockets inside the droplet never get any
+    let outside = search::bfs_distances(&steam, &steam.min);
+    Some(
+        steam
+            .lava
+            .iter()
+            .flat_map(neighbours)
+            .filter(|c| outside.contains_key(c))
+            .count(),
+    )
+}
+
+fn main() {
+    let input = &advent_of_code::read_file("inputs", 18);
+    if let Err(e) = parse(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    advent_of_code::solve!(1, part_one, input);
+    advent_of_code::solve!(2, part_two, input);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let input = advent_of_code::read_file("examples", 18);
+        assert_eq!(part_one(&input), Some(64));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let input = advent_of_code::read_file("examples", 18);
+        assert_eq!(part_two(&input), Some(58));
+    }
+
+    #[test]
+    fn test_small_droplets() {
+        assert_eq!(part_one("1,1,1\n2,1,1"), Some(10));
+        assert_eq!(part_two("1,1,1\n2,1,1"), Some(10));
+        assert!(parse("1,1,1\n2,1").unwrap_err().line == 2);
+    }
+}
@@ -0,0 +1,238 @@
+use advent_of_code::helpers::parse::{parse_all, unsigned, ParseError};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, multispace1},
+    multi::separated_list1,
+    sequence::{delimited, preceded, tuple},
+    IResult,
+};
+
+// what robots collect and what they cost, used to index the arrays below
+const ORE: usize = 0;
+const CLAY: usize = 1;
+const OBSIDIAN: usize = 2;
+const GEODE: usize = 3;
+
+pub const PART_ONE_MINUTES: u32 = 24;
+pub const PART_TWO_MINUTES: u32 = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blueprint {
+    id: u32,
+    // costs[robot][resource]
+    costs: [[u32; 4]; 4],
+}
+
+// Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay.
+// Each geode robot costs 2 ore and 7 obsidian.
+// the sentences can be split over lines, like they are in the puzzle text
+fn blueprint(input: &str) -> IResult<&str, Blueprint> {
+    let robot = |name: &'static str| {
+        preceded(
+            tuple((tag("Each "), tag(name), tag(" robot costs "))),
+            unsigned::<u32>,
+        )
+    };
+    let (input, (id, ore, clay, (obsidian_ore, obsidian_clay), (geode_ore, geode_obsidian))) =
+        tuple((
+            delimited(tag("Blueprint "), unsigned, char(':')),
+            delimited(multispace1, robot("ore"), tag(" ore.")),
+            delimited(multispace1, robot("clay"), tag(" ore.")),
+            tuple((
+                preceded(multispace1, robot("obsidian")),
+                delimited(tag(" ore and "), unsigned, tag(" clay.")),
+            )),
+            tuple((
+                preceded(multispace1, robot("geode")),
+                delimited(tag(" ore and "), unsigned, tag(" obsidian.")),
+            )),
+        ))(input)?;
+    let mut costs = [[0; 4]; 4];
+    costs[ORE][ORE] = ore;
+    costs[CLAY][ORE] = clay;
+    costs[OBSIDIAN][ORE] = obsidian_ore;
+    costs[OBSIDIAN][CLAY] = obsidian_clay;
+    costs[GEODE][ORE] = geode_ore;
+    costs[GEODE][OBSIDIAN] = geode_obsidian;
+    Ok((input, Blueprint { id, costs }))
+}
+
+pub fn parse(input: &str) -> Result<Vec<Blueprint>, ParseError> {
+    parse_all(input, separated_list1(multispace1, blueprint))
+}
+
+// where a search branch is up to
+#[derive(Debug, Clone, Copy)]
+struct State {
+    minutes_left: u32,
+    robots: [u32; 4],
+    stock: [u32; 4],
+}
+
+impl Blueprint {
+    // the most geodes this blueprint can open in `minutes`
+    pub fn max_geodes(&self, minutes: u32) -> u32 {
+        // we can only spend so much of anything in a minute, so there's no point collecting it any faster
+        let mut max_robots = [u32::MAX; 4];
+        for resource in [ORE, CLAY, OBSIDIAN] {
+            max_robots[resource] = self.costs.iter().map(|cost| cost[resource]).max().unwrap();
+        }
+        let start = State {
+            minutes_left: minutes,
+            robots: [1, 0, 0, 0],
+            stock: [0; 4],
+        };
+        let mut best = 0;
+        self.search(start, &max_robots, &mut best);
+        best
+    }
+
+    // rather than deciding what to do every minute, decide which robot to build next and skip ahead to when
+    // it's built, pruning any branch that couldn't beat the best so far
+    fn search(&self, state: State, max_robots: &[u32; 4], best: &mut u32) {
+        let t = state.minutes_left;
+        let geodes_if_idle = state.stock[GEODE] + state.robots[GEODE] * t;
+        *best = (*best).max(geodes_if_idle);
+        // even building a geode robot every minute from now on wouldn't be enough
+        if geodes_if_idle + t * t.saturating_sub(1) / 2 <= *best {
+            return;
+        }
+
+        // geode robots first, good plans found early make the pruning bite sooner
+        for robot in [GEODE, OBSIDIAN, CLAY, ORE] {
+            if state.robots[robot] >= max_robots[robot] {
+                continue;
+            }
+            // minutes of collecting before we can afford it, None if we aren't collecting something it needs
+            let wait = (0..GEODE).try_fold(0, |wait, resource| {
+                let cost = self.costs[robot][resource];
+                let (have, rate) = (state.stock[resource], state.robots[resource]);
+                if cost <= have {
+                    Some(wait)
+                } else if rate == 0 {
+                    None
+                } else {
+                    Some(wait.max((cost - have).div_ceil(rate)))
+                }
+            });
+            // it takes another minute to build, and has to be done in time to collect something
+            let Some(elapsed) = wait.map(|wait| wait + 1).filter(|elapsed| *elapsed < t) else {
+                continue;
+            };
+            let mut next = State {
+                minutes_left: t - elapsed,
+                ..state
+            };
+            for resource in 0..4 {
+                next.stock[resource] += state.robots[resource] * elapsed;
+                next.stock[resource] -= self.costs[robot][resource];
+            }
+            next.robots[robot] += 1;
+            self.search(next, max_robots, best);
+        }
+    }
+
+    pub fn quality_level(&self, minutes: u32) -> u32 {
+        self.id * self.max_geodes(minutes)
+    }
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    let blueprints = parse(input).ok()?;
+    Some(
+        blueprints
+            .iter()
+            .map(|bp| bp.quality_level(PART_ONE_MINUTES))
+            .sum(),
+    )
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let blueprints = parse(input).ok()?;
+    // the elephants ate all but the first three
+    Some(
+        blueprints
+            .iter()
+            .take(3)
+            .map(|bp| bp.max_geodes(PART_TWO_MINUTES))
+            .product(),
+    )
+}
+
+fn main() {
+    let input = &advent_of_code::read_file("inputs", 19);
+    let blueprints = parse(input).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // e.g. `cargo solve 19 -- --minutes 28` shows what every blueprint can do in that long instead
+    let mut args = pico_args::Arguments::from_env();
+    match args.opt_value_from_str::<_, u32>("--minutes") {
+        Ok(Some(minutes)) => {
+            for bp in blueprints {
+                println!(
+                    "Blueprint {}: {} geodes in {} minutes",
+                    bp.id,
+                    bp.max_geodes(minutes),
+                    minutes
+                );
+            }
+        }
+        Ok(None) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let input = advent_of_code::read_file("examples", 19);
+        assert_eq!(part_one(&input), Some(33));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let input = advent_of_code::read_file("examples", 19);
+        assert_eq!(part_two(&input), Some(56 * 62));
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = advent_of_code::read_file("examples", 19);
+        let blueprints = parse(&input).unwrap();
+        assert_eq!(
+            blueprints[1],
+            Blueprint {
+                id: 2,
+                costs: [[2, 0, 0, 0], [3, 0, 0, 0], [3, 8, 0, 0], [3, 0, 12, 0]]
+            }
+        );
+
+        // the puzzle text wraps each sentence onto its own line
+        let wrapped = input.replace(". ", ".\n  ").replace(": ", ":\n  ");
+        assert_eq!(parse(&wrapped).unwrap(), blueprints);
+
+        let err = parse(&input.replace("14 clay", "14 mud")).unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn test_minutes() {
+        let input = advent_of_code::read_file("examples", 19);
+        let blueprints = parse(&input).unwrap();
+        assert_eq!(blueprints[0].max_geodes(PART_ONE_MINUTES), 9);
+        assert_eq!(blueprints[1].max_geodes(PART_ONE_MINUTES), 12);
+        // not enough time to get a geode robot going
+        assert_eq!(blueprints[0].max_geodes(10), 0);
+    }
+}
@@ -1,56 +1,131 @@
-use std::collections::VecDeque;
+use advent_of_code::helpers::{ints, IntError};
 
-pub fn parse(input: &str) -> Vec<i64> {
-    input.lines().map(|n| n.parse::<i64>().unwrap()).collect()
-    // [1, 2, -3, 3, -2, 0, 4].to_vec()
+pub const DECRYPTION_KEY: i64 = 811_589_153;
+
+pub fn parse(input: &str) -> Result<Vec<i64>, IntError> {
+    ints::<i64>(input).collect()
 }
 
-// not mine, credit to https://gist.github.com/samueltardieu/3c80720af87d78e5b034afdb57d147b0
-// but it doesn't come up with the right answer for the input set =/
-fn main() {
-    let input = &advent_of_code::read_file("inputs", 20);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+// the file in its current order, as the original index of each number so duplicates don't get mixed up.
+// Split into buckets of about √n numbers, so finding, removing and inserting only walk a bucket or two
+// instead of the whole list
+struct Mixer {
+    buckets: Vec<Vec<usize>>,
+    // which bucket each original index is in
+    bucket_of: Vec<usize>,
+    bucket_size: usize,
 }
 
-pub fn part_one(input: &str) -> Option<i64> {
-    let values = parse(input);
-    let mut values = values
-        .iter()
-        .map(|v| *v)
-        .enumerate()
-        .collect::<VecDeque<_>>();
-
-    for i in 0..values.len() {
-        // find the position of the item in the current list by using the original index
-        let idx = values.iter().enumerate().position(|(j, _)| i == j).unwrap();
-
-        // rotate so that it's at the beginning of the list
-        values.rotate_left(idx);
-        // pop the (orig pos, value) list off
-        let (j, val) = values.pop_front().unwrap();
-        // figure out number of places it should be rotated
-        let d = val.rem_euclid(values.len() as i64) as usize;
-        // rotate to that position
-        values.rotate_left(d);
-        // put the (orig pos, value) tuple back into the list
-        values.push_front((j, val));
+impl Mixer {
+    fn new(len: usize) -> Self {
+        let bucket_size = ((len as f64).sqrt() as usize).max(1);
+        let mut mixer = Self {
+            buckets: vec![(0..len).collect()],
+            bucket_of: vec![0; len],
+            bucket_size,
+        };
+        mixer.rebuild();
+        mixer
+    }
+
+    // evens the buckets back out once inserts have piled up in a few of them
+    fn rebuild(&mut self) {
+        let order = self.order().collect::<Vec<_>>();
+        self.buckets = order
+            .chunks(self.bucket_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        for (b, bucket) in self.buckets.iter().enumerate() {
+            for id in bucket {
+                self.bucket_of[*id] = b;
+            }
+        }
+    }
+
+    fn order(&self) -> impl Iterator<Item = usize> + '_ {
+        self.buckets.iter().flatten().copied()
+    }
+
+    // takes `id` out, returning where it was
+    fn remove(&mut self, id: usize) -> usize {
+        let b = self.bucket_of[id];
+        let offset = self.buckets[b].iter().position(|i| *i == id).unwrap();
+        self.buckets[b].remove(offset);
+        self.buckets[..b]
+            .iter()
+            .map(|bucket| bucket.len())
+            .sum::<usize>()
+            + offset
+    }
+
+    // puts `id` in so it ends up at `position`
+    fn insert(&mut self, id: usize, mut position: usize) {
+        let last = self.buckets.len() - 1;
+        for (b, bucket) in self.buckets.iter_mut().enumerate() {
+            if position <= bucket.len() || b == last {
+                bucket.insert(position, id);
+                self.bucket_of[id] = b;
+                if bucket.len() > 2 * self.bucket_size {
+                    self.rebuild();
+                }
+                return;
+            }
+            position -= bucket.len();
+        }
+    }
+}
+
+// moves every number forwards or backwards by its value, in the order they were originally in, `rounds` times.
+// Returns the numbers in their final order
+pub fn mix(values: &[i64], rounds: usize) -> Vec<i64> {
+    if values.len() < 2 {
+        return values.to_vec();
+    }
+    let mut mixer = Mixer::new(values.len());
+    // with one number taken out there are len - 1 others to go round, so that's a full lap
+    let lap = values.len() as i64 - 1;
+    for _ in 0..rounds {
+        for (id, value) in values.iter().enumerate() {
+            let from = mixer.remove(id) as i64;
+            mixer.insert(id, (from + value).rem_euclid(lap) as usize);
+        }
     }
-    // now that it's all rotated, find where our 0 went
-    let idx = values
-        .iter()
-        .enumerate()
-        .position(|(_, (_, v))| (*v == 0))
-        .unwrap();
+    mixer.order().map(|id| values[id]).collect()
+}
+
+// the 1000th, 2000th and 3000th numbers after the 0, added up
+pub fn grove_coordinates(mixed: &[i64]) -> Option<i64> {
+    let zero = mixed.iter().position(|v| *v == 0)?;
     Some(
-        values[(1000 + idx) % values.len()].1
-            + values[(2000 + idx) % values.len()].1
-            + values[(3000 + idx) % values.len()].1,
+        [1000, 2000, 3000]
+            .iter()
+            .map(|n| mixed[(zero + n) % mixed.len()])
+            .sum(),
     )
 }
 
-pub fn part_two(_input: &str) -> Option<u32> {
-    None
+pub fn part_one(input: &str) -> Option<i64> {
+    let values = parse(input).ok()?;
+    grove_coordinates(&mix(&values, 1))
+}
+
+pub fn part_two(input: &str) -> Option<i64> {
+    let values = parse(input)
+        .ok()?
+        .into_iter()
+        .map(|v| v * DECRYPTION_KEY)
+        .collect::<Vec<_>>();
+    grove_coordinates(&mix(&values, 10))
+}
+
+fn main() {
+    let input = &advent_of_code::read_file("inputs", 20);
+    if let Err(e) = parse(input) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    advent_of_code::solve!(1, part_one, input);
+    advent_of_code::solve!(2, part_two, input);
 }
 
 #[cfg(test)]
@@ -66,6 +141,34 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 20);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(1623178306));
+    }
+
+    // the obvious way, one Vec and a lot of shuffling
+    fn slow_mix(values: &[i64], rounds: usize) -> Vec<i64> {
+        let mut order = (0..values.len()).collect::<Vec<_>>();
+        let lap = values.len() as i64 - 1;
+        for _ in 0..rounds {
+            for (id, value) in values.iter().enumerate() {
+                let from = order.iter().position(|i| *i == id).unwrap();
+                order.remove(from);
+                order.insert((from as i64 + value).rem_euclid(lap) as usize, id);
+            }
+        }
+        order.into_iter().map(|id| values[id]).collect()
+    }
+
+    #[test]
+    fn test_mix() {
+        // after one round the example is 1, 2, -3, 4, 0, 3, -2 - the same circle as the puzzle shows
+        let values = parse(&advent_of_code::read_file("examples", 20)).unwrap();
+        assert_eq!(mix(&values, 1), vec![-2, 1, 2, -3, 4, 0, 3]);
+
+        // plenty of duplicates, and values a lot bigger than the list
+        let values = (0..500i64)
+            .map(|n| (n * 7919 % 61 - 30) * if n % 3 == 0 { DECRYPTION_KEY } else { 1 })
+            .collect::<Vec<_>>();
+        assert_eq!(mix(&values, 3), slow_mix(&values, 3));
+        assert_eq!(mix(&[5], 10), vec![5]);
     }
 }
@@ -2,6 +2,13 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
+pub mod cycle;
+pub mod interval;
+pub mod ocr;
+pub mod parse;
+pub mod search;
+
+pub use interval::IntervalSet;
 
 pub fn manhattan(p1: (i64, i64), p2: (i64, i64)) -> usize {
     (p1.0.abs_diff(p2.0) + p1.1.abs_diff(p2.1)) as usize
@@ -34,3 +41,152 @@ pub fn intersection(
 
     Some(((b2 * c1 - b1 * c2) / delta, (a1 * c2 - a2 * c1) / delta))
 }
+
+pub trait Int: Copy {
+    const SIGNED: bool;
+    // None if it doesn't fit
+    fn from_parts(negative: bool, magnitude: u128) -> Option<Self>;
+}
+
+macro_rules! impl_int {
+    ($signed:expr => $($t:ty),*) => {$(
+        impl Int for $t {
+            const SIGNED: bool = $signed;
+            fn from_parts(negative: bool, magnitude: u128) -> Option<Self> {
+                if negative {
+                    i128::try_from(magnitude).ok()?.checked_neg()?.try_into().ok()
+                } else {
+                    magnitude.try_into().ok()
+                }
+            }
+        }
+    )*};
+}
+
+// a number in the text that's too big for the type we wanted, e.g. 300 as a u8
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntError {
+    pub text: String,
+    pub type_name: &'static str,
+}
+
+impl std::fmt::Display for IntError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} doesn't fit in a {}", self.text, self.type_name)
+    }
+}
+
+impl std::error::Error for IntError {}
+
+impl_int!(true => i8, i16, i32, i64, i128, isize);
+impl_int!(false => u8, u16, u32, u64, u128, usize);
+
+pub struct Ints<'a, T> {
+    bytes: &'a [u8],
+    pos: usize,
+    signed: bool,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Int> Iterator for Ints<'_, T> {
+    type Item = Result<T, IntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.bytes;
+        while self.pos < bytes.len() && !bytes[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        if self.pos == bytes.len() {
+            return None;
+        }
+        // a '-' is only a sign if it isn't sitting between two numbers, so "2-4" is 2 and 4, not 2 and -4
+        let negative = self.signed
+            && self.pos > 0
+            && bytes[self.pos - 1] == b'-'
+            && (self.pos < 2 || !bytes[self.pos - 2].is_ascii_digit());
+        let start = self.pos;
+        // None once it's too big for anything
+        let mut magnitude = Some(0u128);
+        while self.pos < bytes.len() && bytes[self.pos].is_ascii_digit() {
+            let digit = (bytes[self.pos] - b'0') as u128;
+            magnitude = magnitude.and_then(|m| m.checked_mul(10)?.checked_add(digit));
+            self.pos += 1;
+        }
+        Some(
+            magnitude
+                .and_then(|m| T::from_parts(negative, m))
+                .ok_or_else(|| IntError {
+                    text: format!(
+                        "{}{}",
+                        if negative { "-" } else { "" },
+                        String::from_utf8_lossy(&bytes[start..self.pos])
+                    ),
+                    type_name: std::any::type_name::<T>(),
+                }),
+        )
+    }
+}
+
+// every integer in `s`, e.g. `move 3 from 1 to 2` gives 3, 1, 2. `-` is only a sign for signed types, and
+// anything that doesn't fit in a `T` is an error rather than wrapping round
+pub fn ints<T: Int>(s: &str) -> Ints<'_, T> {
+    Ints {
+        bytes: s.as_bytes(),
+        pos: 0,
+        signed: T::SIGNED,
+        _marker: std::marker::PhantomData,
+    }
+}
+
+// `-` is never a sign, e.g. for ranges like 2-4
+pub fn unsigned_ints<T: Int>(s: &str) -> Ints<'_, T> {
+    Ints {
+        signed: false,
+        ..ints(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all<T: Int>(ints: Ints<'_, T>) -> Result<Vec<T>, IntError> {
+        ints.collect()
+    }
+
+    #[test]
+    fn test_ints() {
+        assert_eq!(all(ints::<u32>("move 13 from 2 to 9")), Ok(vec![13, 2, 9]));
+        assert_eq!(
+            all(ints::<i32>(
+                "Sensor at x=-2, y=15: closest beacon is at x=10, y=-16"
+            )),
+            Ok(vec![-2, 15, 10, -16])
+        );
+        assert_eq!(all(ints::<i64>("2-4,6-8")), Ok(vec![2, 4, 6, 8]));
+        assert_eq!(all(ints::<u8>("x=-2")), Ok(vec![2]));
+        assert_eq!(all(unsigned_ints::<i8>("-5 -6")), Ok(vec![5, 6]));
+        assert_eq!(ints::<i16>("no numbers here").next(), None);
+    }
+
+    #[test]
+    fn test_ints_overflow() {
+        assert_eq!(
+            all(ints::<u8>("255,300")),
+            Err(IntError {
+                text: "300".to_string(),
+                type_name: "u8"
+            })
+        );
+        assert_eq!(all(ints::<i8>("-128 127")), Ok(vec![-128, 127]));
+        assert_eq!(ints::<i8>("-129").next().unwrap().unwrap_err().text, "-129");
+        // too big for a u128 while we're still reading it, and the numbers after it carry on as normal
+        let huge = format!("{} 7", "9".repeat(40));
+        let mut it = ints::<u64>(&huge);
+        assert_eq!(
+            it.next().unwrap().unwrap_err().to_string(),
+            format!("{} doesn't fit in a u64", "9".repeat(40))
+        );
+        assert_eq!(it.next(), Some(Ok(7)));
+    }
+}
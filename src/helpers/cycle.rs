@@ -0,0 +1,120 @@
+use std::{collections::HashMap, hash::Hash};
+
+// after `start` steps the state repeats every `length` steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: u64,
+    pub length: u64,
+}
+
+impl Cycle {
+    // the earliest step at the same point in the cycle as `target`
+    pub fn equivalent_step(&self, target: u64) -> u64 {
+        if target < self.start {
+            target
+        } else {
+            self.start + (target - self.start) % self.length
+        }
+    }
+
+    pub fn cycles_skipped(&self, target: u64) -> u64 {
+        target.saturating_sub(self.start) / self.length
+    }
+}
+
+// runs `step` until `key` repeats, values[i] is `value` after i steps. Gives up after `limit` steps
+pub fn find_cycle<S, K: Eq + Hash>(
+    state: &mut S,
+    limit: u64,
+    mut step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+    value: impl Fn(&S) -> i64,
+) -> (Option<Cycle>, Vec<i64>) {
+    let mut seen = HashMap::new();
+    let mut values = vec![];
+    for n in 0..=limit {
+        values.push(value(state));
+        if let Some(first) = seen.insert(key(state), n) {
+            let cycle = Cycle {
+                start: first,
+                length: n - first,
+            };
+            return (Some(cycle), values);
+        }
+        if n < limit {
+            step(state);
+        }
+    }
+    (None, values)
+}
+
+// the value after `target` steps, only simulating until it starts repeating and then assuming `value`
+// grows by the same amount every time round
+pub fn extrapolate<S, K: Eq + Hash>(
+    mut state: S,
+    target: u64,
+    step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+    value: impl Fn(&S) -> i64,
+) -> i64 {
+    match find_cycle(&mut state, target, step, key, value) {
+        (None, values) => values[target as usize],
+        (Some(cycle), values) => {
+            let growth =
+                values[(cycle.start + cycle.length) as usize] - values[cycle.start as usize];
+            values[cycle.equivalent_step(target) as usize]
+                + growth * cycle.cycles_skipped(target) as i64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        // 3, 6, 5, 3, 6, 5, ... under n -> 2n mod 7
+        let mut state = 3;
+        let (cycle, values) = find_cycle(&mut state, 100, |n| *n = *n * 2 % 7, |n| *n, |n| *n);
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                start: 0,
+                length: 3
+            })
+        );
+        assert_eq!(values, vec![3, 6, 5, 3]);
+
+        let (cycle, _) = find_cycle(&mut 0, 10, |n| *n += 1, |n| *n, |n| *n);
+        assert_eq!(cycle, None);
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // walks 0, 1, 2, 3, then loops around 4 positions, gaining 10 every lap
+        let step = |s: &mut (i64, i64)| {
+            s.0 += 1;
+            if s.0 == 8 {
+                s.0 = 4;
+                s.1 += 10;
+            }
+        };
+        let value = |s: &(i64, i64)| s.1 + s.0;
+        let naive = |target: u64| {
+            let mut state = (0, 0);
+            (0..target).for_each(|_| step(&mut state));
+            value(&state)
+        };
+        for target in [0, 3, 4, 7, 8, 9, 100, 1001] {
+            assert_eq!(
+                extrapolate((0, 0), target, step, |s| s.0, value),
+                naive(target)
+            );
+        }
+        assert_eq!(
+            extrapolate((0, 0), 1_000_000_000_000, step, |s| s.0, value),
+            2_499_999_999_994
+        );
+    }
+}
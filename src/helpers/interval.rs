@@ -0,0 +1,174 @@
+use std::ops::Range;
+
+// sorted, disjoint, half-open ranges, touching ones get merged so 0..3 and 3..5 end up as 0..5
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, range: Range<i64>) {
+        if range.is_empty() {
+            return;
+        }
+        // first range that could merge with the new one, and first one strictly after it
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = if lo < hi {
+            self.ranges[lo].start.min(range.start)..self.ranges[hi - 1].end.max(range.end)
+        } else {
+            range
+        };
+        self.ranges.splice(lo..hi, std::iter::once(merged));
+    }
+
+    // may split a range in two
+    pub fn remove(&mut self, range: Range<i64>) {
+        if range.is_empty() {
+            return;
+        }
+        let lo = self.ranges.partition_point(|r| r.end <= range.start);
+        let hi = self.ranges.partition_point(|r| r.start < range.end);
+        if lo >= hi {
+            return;
+        }
+        let mut leftovers = Vec::with_capacity(2);
+        if self.ranges[lo].start < range.start {
+            leftovers.push(self.ranges[lo].start..range.start);
+        }
+        if self.ranges[hi - 1].end > range.end {
+            leftovers.push(range.end..self.ranges[hi - 1].end);
+        }
+        self.ranges.splice(lo..hi, leftovers);
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(idx).is_some_and(|r| r.contains(&value))
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        other.iter().for_each(|r| result.insert(r.clone()));
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        other.iter().for_each(|r| result.remove(r.clone()));
+        result
+    }
+
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(|r| r.start.abs_diff(r.end)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<i64>> {
+        self.ranges.iter()
+    }
+
+    // the gaps inside `bound`
+    pub fn gaps(&self, bound: Range<i64>) -> impl Iterator<Item = Range<i64>> + '_ {
+        let mut cursor = bound.start;
+        self.ranges
+            .iter()
+            .filter(move |r| r.end > bound.start && r.start < bound.end)
+            .map(Some)
+            .chain(std::iter::once(None))
+            .filter_map(move |r| {
+                let next_start = r.map_or(bound.end, |r| r.start.min(bound.end));
+                let gap = cursor..next_start;
+                if let Some(r) = r {
+                    cursor = r.end;
+                }
+                if gap.is_empty() {
+                    None
+                } else {
+                    Some(gap)
+                }
+            })
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range<i64>>>(iter: T) -> Self {
+        let mut set = IntervalSet::new();
+        iter.into_iter().for_each(|r| set.insert(r));
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_merges() {
+        let set: IntervalSet = vec![5..8, 0..3, 3..4, 10..12, 7..11].into_iter().collect();
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![0..4, 5..12]);
+        assert_eq!(set.len(), 11);
+        assert!(set.contains(3));
+        assert!(!set.contains(4));
+    }
+
+    #[test]
+    fn test_remove_splits() {
+        let mut set: IntervalSet = std::iter::once(0..10).collect();
+        set.remove(3..5);
+        set.remove(9..20);
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![0..3, 5..9]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: IntervalSet = vec![0..5, 10..15].into_iter().collect();
+        let b: IntervalSet = std::iter::once(3..12).collect();
+        assert_eq!(a.union(&b).iter().cloned().collect::<Vec<_>>(), vec![0..15]);
+        assert_eq!(
+            a.intersection(&b).iter().cloned().collect::<Vec<_>>(),
+            vec![3..5, 10..12]
+        );
+        assert_eq!(
+            a.difference(&b).iter().cloned().collect::<Vec<_>>(),
+            vec![0..3, 12..15]
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let set: IntervalSet = vec![-5..2, 4..6, 8..30].into_iter().collect();
+        assert_eq!(set.gaps(0..20).collect::<Vec<_>>(), vec![2..4, 6..8]);
+        assert_eq!(set.gaps(-10..-3).collect::<Vec<_>>(), vec![-10..-5]);
+        assert_eq!(
+            IntervalSet::new().gaps(0..3).collect::<Vec<_>>(),
+            vec![0..3]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+// the letters AoC draws on its 6 pixel high screens (e.g. 2022 day 10)
+const SMALL_FONT: &[(char, &str)] = &[
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+// the bigger letters used by the 10 pixel high puzzles (e.g. 2018 day 10)
+#[rustfmt::skip]
+const LARGE_FONT: &[(char, &str)] = &[
+    ('A', "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#"),
+    ('B', "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####."),
+    ('C', ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####."),
+    ('E', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('F', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('G', ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#"),
+    ('H', "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#"),
+    ('J', "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###.."),
+    ('K', "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#"),
+    ('L', "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('N', "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#"),
+    ('P', "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('R', "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#"),
+    ('X', "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#"),
+    ('Z', "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    // we only know fonts that are 6 or 10 pixels high
+    UnsupportedHeight(usize),
+    // (column the glyph starts at, the glyph as drawn)
+    UnknownGlyph(usize, String),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "no font is {} pixels high, expected 6 or 10", height)
+            }
+            OcrError::UnknownGlyph(column, glyph) => {
+                write!(f, "unrecognised letter at column {}:\n{}", column, glyph)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+// draws columns `from..to` of every row as '#'/'.' lines
+fn draw<R: AsRef<[bool]>>(rows: &[R], from: usize, to: usize) -> String {
+    rows.iter()
+        .map(|row| {
+            (from..to)
+                .map(|x| match row.as_ref().get(x) {
+                    Some(true) => '#',
+                    _ => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// parses a font glyph and draws it again without any blank columns either side, e.g. the small 'I'
+fn trimmed(glyph: &str) -> String {
+    let rows = glyph
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let lit = |x: usize| rows.iter().any(|row| row[x]);
+    let from = (0..width).find(|x| lit(*x)).unwrap_or(0);
+    let to = (0..width).rev().find(|x| lit(*x)).map_or(0, |x| x + 1);
+    draw(&rows, from, to)
+}
+
+// the small letters are 4 wide with a blank column after them, apart from 'Y' which runs straight into the
+// next letter, so they're told apart by where they sit rather than by the gaps
+const SMALL_PITCH: usize = 5;
+
+// (first column, one past the last) of each letter, trimmed down to its lit columns
+fn letter_spans(
+    lit: impl Fn(usize) -> bool,
+    width: usize,
+    pitch: Option<usize>,
+) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    match pitch {
+        Some(pitch) => {
+            for cell in (0..width).step_by(pitch) {
+                let end = (cell + pitch).min(width);
+                if let Some(from) = (cell..end).find(|x| lit(*x)) {
+                    let to = (cell..end).rev().find(|x| lit(*x)).unwrap() + 1;
+                    spans.push((from, to));
+                }
+            }
+        }
+        // the large font always has a gap, so anything lit without one in between is a single letter
+        None => {
+            let mut x = 0;
+            while x < width {
+                if !lit(x) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < width && lit(x) {
+                    x += 1;
+                }
+                spans.push((start, x));
+            }
+        }
+    }
+    spans
+}
+
+// one slice of lit (true) or unlit pixels per row, starting from the left edge of the screen
+pub fn recognise<R: AsRef<[bool]>>(rows: &[R]) -> Result<String, OcrError> {
+    let (font, pitch) = match rows.len() {
+        6 => (SMALL_FONT, Some(SMALL_PITCH)),
+        10 => (LARGE_FONT, None),
+        height => return Err(OcrError::UnsupportedHeight(height)),
+    };
+    let width = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+    let lit = |x: usize| rows.iter().any(|row| row.as_ref().get(x) == Some(&true));
+
+    let mut letters = String::new();
+    for (start, end) in letter_spans(lit, width, pitch) {
+        let glyph = draw(rows, start, end);
+        let letter = font
+            .iter()
+            .find(|(_, pattern)| trimmed(pattern) == glyph)
+            .map(|(letter, _)| *letter)
+            .ok_or(OcrError::UnknownGlyph(start, glyph))?;
+        letters.push(letter);
+    }
+    Ok(letters)
+}
+
+// for a screen drawn with # for lit pixels
+pub fn recognise_str(screen: &str) -> Result<String, OcrError> {
+    let rows = screen
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    recognise(&rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_font() {
+        let screen = "
+###..####.####.#..#.####.####.#..#..##..
+#..#....#.#....#.#..#....#....#..#.#..#.
+#..#...#..###..##...###..###..####.#..#.
+###...#...#....#.#..#....#....#..#.####.
+#.#..#....#....#.#..#....#....#..#.#..#.
+#..#.####.####.#..#.####.#....#..#.#..#.";
+        assert_eq!(recognise_str(screen), Ok("RZEKEFHA".to_string()));
+
+        let pixels = screen
+            .lines()
+            .skip(1)
+            .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(recognise(&pixels), Ok("RZEKEFHA".to_string()));
+    }
+
+    // the letters side by side, each padded out to `pitch` columns
+    fn side_by_side(glyphs: &[&str], pitch: usize) -> String {
+        let height = glyphs[0].lines().count();
+        let mut rows = vec![String::new(); height];
+        for glyph in glyphs {
+            for (row, line) in rows.iter_mut().zip(glyph.lines()) {
+                row.push_str(&format!("{:.<width$}", line, width = pitch));
+            }
+        }
+        rows.join("\n")
+    }
+
+    #[test]
+    fn test_every_glyph() {
+        // the small letters sit on their pitch, the large ones with a couple of blank columns between them
+        for (font, pitch) in [(SMALL_FONT, SMALL_PITCH), (LARGE_FONT, 8)] {
+            let glyphs = font.iter().map(|(_, glyph)| *glyph).collect::<Vec<_>>();
+            let expected = font.iter().map(|(letter, _)| *letter).collect::<String>();
+            assert_eq!(recognise_str(&side_by_side(&glyphs, pitch)), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_adjacent_letters() {
+        let glyph = |c: char| SMALL_FONT.iter().find(|(l, _)| *l == c).unwrap().1;
+        // no blank column between the Y and the letter after it
+        let screen = side_by_side(
+            &[glyph('Y'), glyph('L'), glyph('Y'), glyph('H')],
+            SMALL_PITCH,
+        );
+        assert!(screen.starts_with("#...##..."));
+        assert_eq!(recognise_str(&screen), Ok("YLYH".to_string()));
+        // and an I, which starts with a blank column, straight after one
+        let screen = side_by_side(&[glyph('Y'), glyph('I')], SMALL_PITCH);
+        assert_eq!(recognise_str(&screen), Ok("YI".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(recognise_str("#\n#"), Err(OcrError::UnsupportedHeight(2)));
+        assert_eq!(
+            recognise_str("..#\n..#\n..#\n..#\n..#\n..#"),
+            Err(OcrError::UnknownGlyph(2, "#\n#\n#\n#\n#\n#".to_string()))
+        );
+    }
+}
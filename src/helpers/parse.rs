@@ -0,0 +1,188 @@
+use std::{fmt, str::FromStr};
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{
+        char, digit1, line_ending, multispace0, none_of, one_of, space0, space1,
+    },
+    combinator::{all_consuming, map_opt, map_res, opt, recognize},
+    multi::{many1, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult, Parser,
+};
+
+// where in the input things went wrong, line and column are 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    // in chars, not bytes
+    pub column: usize,
+    pub message: String,
+    // the whole offending line
+    pub context: String,
+}
+
+impl ParseError {
+    // `remaining` is the part of `input` from where it went wrong, so checks after parsing can point somewhere too
+    pub fn at(input: &str, remaining: &str, message: String) -> Self {
+        let offset = input.len() - remaining.len();
+        let consumed = &input[..offset];
+        let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+            message,
+            context: input[line_start..].lines().next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )?;
+        writeln!(f, "{}", self.context)?;
+        write!(f, "{:>width$}", "^", width = self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// the whole input has to parse, apart from trailing whitespace
+pub fn parse_all<'a, T>(
+    input: &'a str,
+    parser: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> Result<T, ParseError> {
+    match all_consuming(terminated(parser, multispace0))(input) {
+        Ok((_, value)) => Ok(value),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            let message = if e.code == nom::error::ErrorKind::Eof {
+                "unexpected trailing input".to_string()
+            } else {
+                format!("expected {}", e.code.description().to_lowercase())
+            };
+            Err(ParseError::at(input, e.input, message))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(ParseError::at(
+            input,
+            "",
+            "unexpected end of input".to_string(),
+        )),
+    }
+}
+
+// e.g. -12 or +3
+pub fn signed<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(input)
+}
+
+pub fn unsigned<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+// e.g. 79, 98
+pub fn comma_list<'a, T>(
+    item: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    separated_list1(delimited(space0, char(','), space0), item)
+}
+
+// e.g. 1 2 3
+pub fn space_list<'a, T>(
+    item: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    separated_list1(space1, item)
+}
+
+// separated by blank lines
+pub fn blocks<'a, T>(
+    block: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    separated_list1(pair(line_ending, line_ending), block)
+}
+
+// e.g. `  Test: divisible by 23`, just the value
+pub fn labelled<'a, T>(
+    label: &'a str,
+    value: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, T> {
+    preceded(tuple((space0, tag(label), char(':'), space0)), value)
+}
+
+// one row per line, `cell` returns None for characters that aren't allowed
+pub fn grid<'a, T>(
+    cell: impl Fn(char) -> Option<T>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<T>>> {
+    separated_list1(line_ending, many1(map_opt(none_of("\r\n"), cell)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_and_lists() {
+        assert_eq!(signed::<i32>("-12 rest"), Ok((" rest", -12)));
+        assert_eq!(signed::<i64>("+7"), Ok(("", 7)));
+        assert!(unsigned::<u8>("-1").is_err());
+        assert!(unsigned::<u8>("300").is_err());
+        assert_eq!(
+            comma_list(unsigned::<u32>)("79, 98,3"),
+            Ok(("", vec![79, 98, 3]))
+        );
+        assert_eq!(
+            space_list(signed::<i8>)("1 -2  3"),
+            Ok(("", vec![1, -2, 3]))
+        );
+    }
+
+    #[test]
+    fn test_blocks_and_labels() {
+        let input = "a: 1, 2\n  b: -3\n\na: 4\n  b: 5";
+        let block = |input| {
+            let (input, a) =
+                terminated(labelled("a", comma_list(unsigned::<u8>)), line_ending)(input)?;
+            let (input, b) = labelled("b", signed::<i8>)(input)?;
+            Ok((input, (a, b)))
+        };
+        assert_eq!(
+            parse_all(input, blocks(block)),
+            Ok(vec![(vec![1, 2], -3), (vec![4], 5)])
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let cell = |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        };
+        assert_eq!(
+            parse_all("#.\n.#\n", grid(cell)),
+            Ok(vec![vec![true, false], vec![false, true]])
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        let err = parse_all(
+            "1, 2\n3, x",
+            separated_list1(line_ending, comma_list(unsigned::<u32>)),
+        )
+        .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.context, "3, x");
+        assert_eq!(
+            err.to_string(),
+            "unexpected trailing input at line 2, column 2\n3, x\n ^"
+        );
+
+        let err = parse_all("x", unsigned::<u32>).unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        assert_eq!(err.message, "expected digit");
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use priority_queue::PriorityQueue;
+
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    // (node, cost of the step) for everywhere one step away
+    fn neighbours(&self, node: &Self::Node) -> Vec<(Self::Node, usize)>;
+}
+
+pub fn reconstruct_path<N: Clone + Eq + Hash>(came_from: &HashMap<N, N>, goal: &N) -> Vec<N> {
+    let mut total_path = vec![goal.clone()];
+    let mut curr = goal;
+    while let Some(prev) = came_from.get(curr) {
+        total_path.push(prev.clone());
+        curr = prev;
+    }
+    total_path.reverse();
+    total_path
+}
+
+// fewest hops to the first goal found, step costs are ignored
+pub fn bfs<G: Graph>(
+    graph: &G,
+    start: &G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<Vec<G::Node>> {
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::from([start.clone()]);
+    let mut seen = HashSet::from([start.clone()]);
+    while let Some(current) = queue.pop_front() {
+        if is_goal(&current) {
+            return Some(reconstruct_path(&came_from, &current));
+        }
+        for (neighbour, _) in graph.neighbours(&current) {
+            if seen.insert(neighbour.clone()) {
+                came_from.insert(neighbour.clone(), current.clone());
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    None
+}
+
+pub fn bfs_distances<G: Graph>(graph: &G, start: &G::Node) -> HashMap<G::Node, usize> {
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start.clone()]);
+    while let Some(current) = queue.pop_front() {
+        let dist = distances[&current];
+        for (neighbour, _) in graph.neighbours(&current) {
+            if !distances.contains_key(&neighbour) {
+                distances.insert(neighbour.clone(), dist + 1);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    distances
+}
+
+// `heuristic` must never overestimate or the path might not be the cheapest
+pub fn a_star<G: Graph>(
+    graph: &G,
+    start: &G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> usize,
+) -> Option<(Vec<G::Node>, usize)> {
+    let mut open_set = PriorityQueue::new();
+    let mut came_from = HashMap::new();
+    let mut g_score = HashMap::from([(start.clone(), 0)]);
+
+    open_set.push(start.clone(), Reverse(heuristic(start)));
+
+    while let Some((current, _)) = open_set.pop() {
+        let current_score = g_score[&current];
+        if is_goal(&current) {
+            return Some((reconstruct_path(&came_from, &current), current_score));
+        }
+        for (neighbour, cost) in graph.neighbours(&current) {
+            let tentative_g_score = current_score + cost;
+            if tentative_g_score < *g_score.get(&neighbour).unwrap_or(&usize::MAX) {
+                // this path to neighbour is better than any previous one, record it
+                came_from.insert(neighbour.clone(), current.clone());
+                g_score.insert(neighbour.clone(), tentative_g_score);
+                let f = tentative_g_score + heuristic(&neighbour);
+                open_set.push_increase(neighbour, Reverse(f));
+            }
+        }
+    }
+    None
+}
+
+pub fn dijkstra<G: Graph>(
+    graph: &G,
+    start: &G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<(Vec<G::Node>, usize)> {
+    a_star(graph, start, is_goal, |_| 0)
+}
+
+pub fn dijkstra_distances<G: Graph>(graph: &G, start: &G::Node) -> HashMap<G::Node, usize> {
+    let mut open_set = PriorityQueue::new();
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    open_set.push(start.clone(), Reverse(0));
+    while let Some((current, Reverse(dist))) = open_set.pop() {
+        for (neighbour, cost) in graph.neighbours(&current) {
+            let candidate = dist + cost;
+            if candidate < *distances.get(&neighbour).unwrap_or(&usize::MAX) {
+                distances.insert(neighbour.clone(), candidate);
+                open_set.push_increase(neighbour, Reverse(candidate));
+            }
+        }
+    }
+    distances
+}
+
+// paths can go through other nodes, handy for collapsing a graph down to the interesting bits
+pub fn all_pairs_shortest_paths<G: Graph>(
+    graph: &G,
+    nodes: &[G::Node],
+) -> HashMap<(G::Node, G::Node), usize> {
+    nodes
+        .iter()
+        .flat_map(|from| {
+            let distances = dijkstra_distances(graph, from);
+            nodes.iter().filter_map(move |to| {
+                distances
+                    .get(to)
+                    .map(|dist| ((from.clone(), to.clone()), *dist))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 4x4 grid where moving right costs 1 and moving down costs 3, with a wall at (1, 1)
+    struct Grid;
+
+    impl Graph for Grid {
+        type Node = (usize, usize);
+
+        fn neighbours(&self, &(x, y): &Self::Node) -> Vec<(Self::Node, usize)> {
+            [((x + 1, y), 1), ((x, y + 1), 3)]
+                .into_iter()
+                .filter(|((x, y), _)| *x < 4 && *y < 4 && (*x, *y) != (1, 1))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let path = bfs(&Grid, &(0, 0), |n| *n == (1, 2)).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(bfs_distances(&Grid, &(0, 0))[&(3, 3)], 6);
+        assert_eq!(bfs(&Grid, &(3, 3), |n| *n == (0, 0)), None);
+    }
+
+    #[test]
+    fn test_weighted_search() {
+        let (path, cost) = dijkstra(&Grid, &(0, 0), |n| *n == (3, 3)).unwrap();
+        assert_eq!(cost, 12);
+        assert_eq!(path[..4], [(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        let (_, cost) = a_star(
+            &Grid,
+            &(0, 0),
+            |n| *n == (3, 3),
+            |&(x, y)| (3 - x) + 3 * (3 - y),
+        )
+        .unwrap();
+        assert_eq!(cost, 12);
+        assert_eq!(dijkstra_distances(&Grid, &(0, 0))[&(1, 2)], 7);
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        let distances = all_pairs_shortest_paths(&Grid, &[(0, 0), (2, 2), (0, 3)]);
+        assert_eq!(distances[&((0, 0), (2, 2))], 8);
+        assert_eq!(distances[&((0, 0), (0, 3))], 9);
+        assert_eq!(distances[&((2, 2), (2, 2))], 0);
+        assert!(!distances.contains_key(&((2, 2), (0, 3))));
+    }
+}